@@ -0,0 +1,228 @@
+#[cfg(test)]
+#[path = "access_control_test.rs"]
+mod access_control_test;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use teloxide::types::{Update, UpdateKind};
+
+use crate::bloc_event::BlocEvent;
+use crate::parsed_command::ParsedCommand;
+
+/// Denials remembered for replying once, forgotten all at once beyond this.
+const MAX_REPLIED: usize = 10_000;
+
+/// Chat, user and reason of a denial the sender was already told about.
+type DeniedReply = (i64, Option<u64>, AccessDeniedReason);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Role {
+    User,
+    Admin,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AccessDeniedReason {
+    BlockedUser,
+    BlockedChat,
+    UserNotAllowed,
+    ChatNotAllowed,
+    MissingRole { command: String, role: Role },
+}
+
+impl fmt::Display for AccessDeniedReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            AccessDeniedReason::BlockedUser => f.write_str("BlockedUser"),
+            AccessDeniedReason::BlockedChat => f.write_str("BlockedChat"),
+            AccessDeniedReason::UserNotAllowed => f.write_str("UserNotAllowed"),
+            AccessDeniedReason::ChatNotAllowed => f.write_str("ChatNotAllowed"),
+            AccessDeniedReason::MissingRole { command, role } => f.write_str(&format!(
+                "MissingRole{{command:{}, role:{:?}}}",
+                command, role
+            )),
+        }
+    }
+}
+
+/// Who may talk to the bot. Everyone is allowed until the first `allow_*`
+/// call, users with an assigned role are always allowed, blocks win over
+/// everything.
+#[derive(Clone, Debug, Default)]
+pub struct AccessControl {
+    allowed_users: Option<HashSet<u64>>,
+    blocked_users: HashSet<u64>,
+    allowed_chats: Option<HashSet<i64>>,
+    blocked_chats: HashSet<i64>,
+    roles: HashMap<u64, Role>,
+    command_roles: HashMap<String, Role>,
+    denied_reply: Option<String>,
+    replied: Arc<Mutex<HashSet<DeniedReply>>>,
+}
+
+impl AccessControl {
+    pub fn new() -> AccessControl {
+        AccessControl::default()
+    }
+
+    pub fn allow_user(mut self, user_id: u64) -> AccessControl {
+        self.allowed_users
+            .get_or_insert_with(HashSet::new)
+            .insert(user_id);
+        self
+    }
+
+    pub fn block_user(mut self, user_id: u64) -> AccessControl {
+        self.blocked_users.insert(user_id);
+        self
+    }
+
+    pub fn allow_chat(mut self, chat_id: i64) -> AccessControl {
+        self.allowed_chats
+            .get_or_insert_with(HashSet::new)
+            .insert(chat_id);
+        self
+    }
+
+    pub fn block_chat(mut self, chat_id: i64) -> AccessControl {
+        self.blocked_chats.insert(chat_id);
+        self
+    }
+
+    pub fn with_role(mut self, user_id: u64, role: Role) -> AccessControl {
+        self.roles.insert(user_id, role);
+        self
+    }
+
+    pub fn require_role(mut self, command: impl Into<String>, role: Role) -> AccessControl {
+        let command = command.into().trim_start_matches('/').to_lowercase();
+        self.command_roles.insert(command, role);
+        self
+    }
+
+    /// Sent to the chat the first time a user, or a chat that isn't allowed,
+    /// is denied. Denied callback queries are answered with it every time.
+    pub fn with_denied_reply(mut self, text: impl Into<String>) -> AccessControl {
+        self.denied_reply = Some(text.into());
+        self
+    }
+
+    pub fn denied_reply(&self) -> Option<&str> {
+        self.denied_reply.as_deref()
+    }
+
+    pub fn role(&self, user_id: u64) -> Role {
+        self.roles.get(&user_id).copied().unwrap_or(Role::User)
+    }
+
+    pub fn check(
+        &self,
+        user_id: Option<u64>,
+        chat_id: Option<i64>,
+        command: Option<&str>,
+    ) -> Result<(), AccessDeniedReason> {
+        if let Some(user_id) = user_id {
+            if self.blocked_users.contains(&user_id) {
+                return Err(AccessDeniedReason::BlockedUser);
+            }
+        }
+
+        if let Some(chat_id) = chat_id {
+            if self.blocked_chats.contains(&chat_id) {
+                return Err(AccessDeniedReason::BlockedChat);
+            }
+
+            if let Some(allowed_chats) = &self.allowed_chats {
+                if !allowed_chats.contains(&chat_id) {
+                    return Err(AccessDeniedReason::ChatNotAllowed);
+                }
+            }
+        }
+
+        if let (Some(user_id), Some(allowed_users)) = (user_id, &self.allowed_users) {
+            if !allowed_users.contains(&user_id) && !self.roles.contains_key(&user_id) {
+                return Err(AccessDeniedReason::UserNotAllowed);
+            }
+        }
+
+        if let Some(command) = command {
+            if let Some(role) = self.command_roles.get(&command.to_lowercase()) {
+                let has_role = user_id.map(|user_id| self.role(user_id) >= *role);
+
+                if has_role != Some(true) {
+                    return Err(AccessDeniedReason::MissingRole {
+                        command: command.to_lowercase(),
+                        role: *role,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Commands addressed to another bot are checked like plain messages.
+    pub(crate) fn check_update(
+        &self,
+        update: &Update,
+        bot_username: &str,
+    ) -> Result<(), AccessDeniedReason> {
+        let command = match &update.kind {
+            UpdateKind::Message(message) => message
+                .text()
+                .and_then(ParsedCommand::parse)
+                .filter(|command| command.is_addressed_to(bot_username)),
+            _ => None,
+        };
+
+        self.check(
+            update.user().map(|user| user.id.0),
+            update.chat().map(|chat| chat.id.0),
+            command.as_ref().map(|command| command.name.as_str()),
+        )
+    }
+
+    /// Returns the events telling the sender about a denied update.
+    pub(crate) fn denied_events(
+        &self,
+        update: &Update,
+        reason: &AccessDeniedReason,
+    ) -> Vec<BlocEvent> {
+        if let UpdateKind::CallbackQuery(callback_query) = &update.kind {
+            return vec![BlocEvent::AnswerCallbackQuery {
+                callback_query_id: callback_query.id.clone(),
+                text: self.denied_reply.clone(),
+                show_alert: false,
+                url: None,
+                cache_time: None,
+            }];
+        }
+
+        let (chat_id, text) = match (update.chat(), &self.denied_reply) {
+            (Some(chat), Some(text)) => (chat.id.0, text.clone()),
+            _ => return Vec::new(),
+        };
+
+        // A chat that isn't allowed is told once, not once for every member.
+        let user_id = match reason {
+            AccessDeniedReason::BlockedChat | AccessDeniedReason::ChatNotAllowed => None,
+            _ => update.user().map(|user| user.id.0),
+        };
+
+        let first = {
+            let mut replied = self.replied.lock().unwrap();
+            if replied.len() >= MAX_REPLIED {
+                replied.clear();
+            }
+
+            replied.insert((chat_id, user_id, reason.clone()))
+        };
+
+        match first {
+            true => vec![BlocEvent::TextToChatSend { chat_id, text }],
+            false => Vec::new(),
+        }
+    }
+}
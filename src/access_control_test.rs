@@ -0,0 +1,119 @@
+use serde_json::json;
+
+use crate::bloc_event::BlocEvent;
+use crate::test_fixtures::{callback_query_json, message_json, update, update_from_text};
+
+use super::{AccessControl, AccessDeniedReason, Role};
+
+#[test]
+fn everyone_is_allowed_by_default() {
+    let access_control = AccessControl::new();
+
+    assert_eq!(
+        access_control.check(Some(1), Some(1), Some("start")),
+        Ok(())
+    );
+}
+
+#[test]
+fn only_allowed_users_and_role_holders_pass() {
+    let access_control = AccessControl::new().allow_user(1).with_role(2, Role::Admin);
+
+    assert_eq!(access_control.check(Some(1), None, None), Ok(()));
+    assert_eq!(access_control.check(Some(2), None, None), Ok(()));
+    assert_eq!(
+        access_control.check(Some(3), None, None),
+        Err(AccessDeniedReason::UserNotAllowed)
+    );
+}
+
+#[test]
+fn blocks_win_over_allowlists() {
+    let access_control = AccessControl::new()
+        .allow_user(1)
+        .block_user(1)
+        .allow_chat(10)
+        .block_chat(20);
+
+    assert_eq!(
+        access_control.check(Some(1), Some(10), None),
+        Err(AccessDeniedReason::BlockedUser)
+    );
+    assert_eq!(
+        access_control.check(None, Some(20), None),
+        Err(AccessDeniedReason::BlockedChat)
+    );
+    assert_eq!(
+        access_control.check(None, Some(30), None),
+        Err(AccessDeniedReason::ChatNotAllowed)
+    );
+}
+
+#[test]
+fn commands_require_roles() {
+    let access_control = AccessControl::new()
+        .with_role(1, Role::Admin)
+        .require_role("/ban", Role::Admin);
+
+    let admin = update_from_text(1, 1, "/ban@OurBot 42");
+    let user = update_from_text(2, 2, "/ban 42");
+    let other_command = update_from_text(2, 2, "/help");
+
+    assert_eq!(access_control.check_update(&admin, "OurBot"), Ok(()));
+    assert_eq!(
+        access_control.check_update(&user, "OurBot"),
+        Err(AccessDeniedReason::MissingRole {
+            command: "ban".to_string(),
+            role: Role::Admin
+        })
+    );
+    assert_eq!(
+        access_control.check_update(&other_command, "OurBot"),
+        Ok(())
+    );
+
+    let foreign = update_from_text(2, 2, "/ban@OtherBot 42");
+    assert_eq!(access_control.check_update(&foreign, "OurBot"), Ok(()));
+}
+
+#[test]
+fn denied_reply_is_sent_once() {
+    let access_control = AccessControl::new()
+        .allow_user(1)
+        .with_denied_reply("Not for you.");
+    let update = update_from_text(2, 2, "hello");
+    let reason = access_control.check_update(&update, "OurBot").unwrap_err();
+
+    assert!(matches!(
+        access_control.denied_events(&update, &reason).as_slice(),
+        [BlocEvent::TextToChatSend { chat_id: 2, .. }]
+    ));
+    assert!(access_control.denied_events(&update, &reason).is_empty());
+}
+
+#[test]
+fn denied_callback_query_is_answered() {
+    let access_control = AccessControl::new()
+        .allow_user(1)
+        .with_denied_reply("Not for you.");
+    let message = message_json(2, 2, "private", json!({"text": "text"}));
+    let update = update(
+        "callback_query",
+        callback_query_json(2, "data", Some(message)),
+    );
+    let reason = access_control.check_update(&update, "OurBot").unwrap_err();
+
+    for _ in 0..2 {
+        match access_control.denied_events(&update, &reason).as_slice() {
+            [BlocEvent::AnswerCallbackQuery {
+                callback_query_id,
+                text,
+                ..
+            }] => {
+                assert_eq!(callback_query_id, "42");
+                assert_eq!(text.as_deref(), Some("Not for you."));
+            }
+            _ => panic!("Expected callback query answer"),
+        }
+    }
+}
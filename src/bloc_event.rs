@@ -1,6 +1,12 @@
 use std::fmt;
 
-use teloxide::types::{ReplyMarkup, InputMedia};
+use chrono::{DateTime, Utc};
+use teloxide::types::{ChatPermissions, InlineQueryResult, InputMedia, PollType, ReplyMarkup};
+use url::Url;
+
+use crate::chat_administration::PromotionRights;
+use crate::download::DownloadWriter;
+use crate::media_source::MediaSource;
 
 #[derive(Clone)]
 pub enum BlocEvent {
@@ -20,10 +26,90 @@ pub enum BlocEvent {
         file_path: String,
         destination_path: String,
     },
+    FetchFile {
+        file_id: String,
+        destination_path: String,
+    },
+    DownloadFileToMemory {
+        file_path: String,
+        max_size: usize,
+    },
+    DownloadFileToWriter {
+        file_path: String,
+        writer: DownloadWriter,
+    },
+    /// Uploads local files every time, even with `UploadedFiles`. Use
+    /// `MediaSourcesToChatSend` with `MediaSource::path` to reuse uploads.
     MediaToChatSend {
         chat_id: i64,
         media: Vec<InputMedia>,
     },
+    FileToChatSend {
+        chat_id: i64,
+        file: MediaSource,
+        caption: Option<String>,
+    },
+    /// GIFs are sent as documents, because albums can't hold animations.
+    MediaSourcesToChatSend {
+        chat_id: i64,
+        media: Vec<MediaSource>,
+    },
+    AnswerCallbackQuery {
+        callback_query_id: String,
+        text: Option<String>,
+        show_alert: bool,
+        url: Option<Url>,
+        cache_time: Option<u32>,
+    },
+    AnswerInlineQuery {
+        inline_query_id: String,
+        results: Vec<InlineQueryResult>,
+        cache_time: Option<u32>,
+        is_personal: bool,
+        next_offset: Option<String>,
+    },
+    SendPoll {
+        chat_id: i64,
+        question: String,
+        options: Vec<String>,
+        poll_type: PollType,
+        is_anonymous: bool,
+        allows_multiple_answers: bool,
+        correct_option_id: Option<u8>,
+        explanation: Option<String>,
+        open_period: Option<u16>,
+    },
+    StopPoll {
+        chat_id: i64,
+        message_id: i32,
+    },
+    BanChatMember {
+        chat_id: i64,
+        user_id: u64,
+        until_date: Option<DateTime<Utc>>,
+        revoke_messages: bool,
+    },
+    UnbanChatMember {
+        chat_id: i64,
+        user_id: u64,
+        only_if_banned: bool,
+    },
+    RestrictChatMember {
+        chat_id: i64,
+        user_id: u64,
+        permissions: ChatPermissions,
+        until_date: Option<DateTime<Utc>>,
+    },
+    PromoteChatMember {
+        chat_id: i64,
+        user_id: u64,
+        rights: PromotionRights,
+    },
+    SetChatAdministratorCustomTitle {
+        chat_id: i64,
+        user_id: u64,
+        custom_title: String,
+    },
 }
 
 impl fmt::Display for BlocEvent {
@@ -47,9 +133,111 @@ impl fmt::Display for BlocEvent {
                 "GetFile{{file_path:{}, destination_path:{}}}",
                 file_path, destination_path
             )),
+            BlocEvent::FetchFile {
+                file_id,
+                destination_path,
+            } => f.write_str(&format!(
+                "FetchFile{{file_id:{}, destination_path:{}}}",
+                file_id, destination_path
+            )),
+            BlocEvent::DownloadFileToMemory {
+                file_path,
+                max_size,
+            } => f.write_str(&format!(
+                "DownloadFileToMemory{{file_path:{}, max_size:{}}}",
+                file_path, max_size
+            )),
+            BlocEvent::DownloadFileToWriter { file_path, writer } => f.write_str(&format!(
+                "DownloadFileToWriter{{file_path:{}, writer:{}}}",
+                file_path, writer
+            )),
             BlocEvent::MediaToChatSend { chat_id, .. } => {
                 f.write_str(&format!("MediaToChatSend{{chat_id:{}}}", chat_id))
             }
+            BlocEvent::FileToChatSend { chat_id, file, .. } => f.write_str(&format!(
+                "FileToChatSend{{chat_id:{}, file:{}}}",
+                chat_id, file
+            )),
+            BlocEvent::MediaSourcesToChatSend { chat_id, media } => f.write_str(&format!(
+                "MediaSourcesToChatSend{{chat_id:{}, media:{}}}",
+                chat_id,
+                media.len()
+            )),
+            BlocEvent::AnswerCallbackQuery {
+                callback_query_id,
+                text,
+                ..
+            } => f.write_str(&format!(
+                "AnswerCallbackQuery{{callback_query_id:{}, text:{:?}}}",
+                callback_query_id, text
+            )),
+            BlocEvent::AnswerInlineQuery {
+                inline_query_id,
+                results,
+                next_offset,
+                ..
+            } => f.write_str(&format!(
+                "AnswerInlineQuery{{inline_query_id:{}, results:{}, next_offset:{:?}}}",
+                inline_query_id,
+                results.len(),
+                next_offset
+            )),
+            BlocEvent::SendPoll {
+                chat_id,
+                question,
+                poll_type,
+                ..
+            } => f.write_str(&format!(
+                "SendPoll{{chat_id:{}, question:{}, poll_type:{:?}}}",
+                chat_id, question, poll_type
+            )),
+            BlocEvent::StopPoll {
+                chat_id,
+                message_id,
+            } => f.write_str(&format!(
+                "StopPoll{{chat_id:{}, message_id:{}}}",
+                chat_id, message_id
+            )),
+            BlocEvent::BanChatMember {
+                chat_id,
+                user_id,
+                until_date,
+                ..
+            } => f.write_str(&format!(
+                "BanChatMember{{chat_id:{}, user_id:{}, until_date:{:?}}}",
+                chat_id, user_id, until_date
+            )),
+            BlocEvent::UnbanChatMember {
+                chat_id, user_id, ..
+            } => f.write_str(&format!(
+                "UnbanChatMember{{chat_id:{}, user_id:{}}}",
+                chat_id, user_id
+            )),
+            BlocEvent::RestrictChatMember {
+                chat_id,
+                user_id,
+                permissions,
+                until_date,
+            } => f.write_str(&format!(
+                "RestrictChatMember{{chat_id:{}, user_id:{}, permissions:{:?}, until_date:{:?}}}",
+                chat_id, user_id, permissions, until_date
+            )),
+            BlocEvent::PromoteChatMember {
+                chat_id,
+                user_id,
+                rights,
+            } => f.write_str(&format!(
+                "PromoteChatMember{{chat_id:{}, user_id:{}, rights:{:?}}}",
+                chat_id, user_id, rights
+            )),
+            BlocEvent::SetChatAdministratorCustomTitle {
+                chat_id,
+                user_id,
+                custom_title,
+            } => f.write_str(&format!(
+                "SetChatAdministratorCustomTitle{{chat_id:{}, user_id:{}, custom_title:{}}}",
+                chat_id, user_id, custom_title
+            )),
         }
     }
 }
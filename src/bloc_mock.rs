@@ -41,6 +41,9 @@ impl Default for BlocMock {
     }
 }
 
+// async_trait wraps the `unimplemented!()` bodies in a `let`, which newer
+// clippy reports as a diverging sub-expression.
+#[allow(clippy::diverging_sub_expression)]
 #[async_trait]
 impl BLoC<BlocEvent, BlocState> for BlocMock {
     fn get_controller(&self) -> Sender<BlocEvent> {
@@ -1,6 +1,16 @@
 use std::fmt;
+use std::time::Duration;
 
-use teloxide::types::{File, InputMedia, Message};
+use teloxide::types::{
+    CallbackQuery, ChatMemberStatus, ChatMemberUpdated, ChosenInlineResult, File, InlineQuery,
+    InputMedia, Message, Poll, PollAnswer, Update, User,
+};
+
+use crate::access_control::AccessDeniedReason;
+use crate::captcha::CaptchaFailure;
+use crate::flood_control::ThrottleScope;
+use crate::form::FormValues;
+use crate::parsed_command::ParsedCommand;
 
 #[derive(Clone)]
 pub enum BlocState {
@@ -9,11 +19,62 @@ pub enum BlocState {
     },
     Command {
         message: Box<Message>,
+        command: ParsedCommand,
+    },
+    EditedMessage {
+        message: Box<Message>,
+    },
+    ChannelPost {
+        message: Box<Message>,
+    },
+    EditedChannelPost {
+        message: Box<Message>,
+    },
+    MyChatMember {
+        chat_id: i64,
+        old_status: ChatMemberStatus,
+        new_status: ChatMemberStatus,
+        chat_member_updated: Box<ChatMemberUpdated>,
+    },
+    ChatMember {
+        chat_id: i64,
+        old_status: ChatMemberStatus,
+        new_status: ChatMemberStatus,
+        chat_member_updated: Box<ChatMemberUpdated>,
+    },
+    NewChatMembers {
+        chat_id: i64,
+        members: Vec<User>,
+        message: Box<Message>,
+    },
+    LeftChatMember {
+        chat_id: i64,
+        member: User,
+        message: Box<Message>,
+    },
+    CallbackQuery {
+        callback_query: Box<CallbackQuery>,
+    },
+    InlineQuery {
+        inline_query: Box<InlineQuery>,
+    },
+    ChosenInlineResult {
+        chosen_inline_result: Box<ChosenInlineResult>,
+    },
+    Poll {
+        poll: Box<Poll>,
+    },
+    PollAnswer {
+        poll_answer: Box<PollAnswer>,
     },
     TextToChatSendSuccessful {
         chat_id: i64,
         text: String,
     },
+    TextToChatSendUnsuccessful {
+        chat_id: i64,
+        text: String,
+    },
     GetFileSuccessful {
         file_id: String,
         file: File,
@@ -24,15 +85,177 @@ pub enum BlocState {
     DownloadFileSuccessful {
         file_path: String,
         destination_path: String,
+        sha256: Option<String>,
     },
     DownloadFileUnsuccessful {
         file_path: String,
         destination_path: String,
     },
+    FileCacheHit {
+        file_unique_id: String,
+    },
+    FileCacheMiss {
+        file_unique_id: String,
+    },
+    DownloadFileProgress {
+        file_path: String,
+        received: usize,
+        total: Option<usize>,
+    },
+    FetchFileSuccessful {
+        file_id: String,
+        file: File,
+        destination_path: String,
+        sha256: Option<String>,
+    },
+    FetchFileUnsuccessful {
+        file_id: String,
+        destination_path: String,
+    },
+    DownloadFileToMemorySuccessful {
+        file_path: String,
+        bytes: Vec<u8>,
+    },
+    DownloadFileToMemoryUnsuccessful {
+        file_path: String,
+    },
+    DownloadFileToWriterSuccessful {
+        file_path: String,
+        size: usize,
+    },
+    DownloadFileToWriterUnsuccessful {
+        file_path: String,
+    },
     MediaToChatSendSuccessful {
         chat_id: i64,
         media: Vec<InputMedia>,
     },
+    MediaToChatSendUnsuccessful {
+        chat_id: i64,
+    },
+    FileToChatSendSuccessful {
+        chat_id: i64,
+        message: Box<Message>,
+    },
+    FileToChatSendUnsuccessful {
+        chat_id: i64,
+        file_name: String,
+    },
+    AnswerCallbackQuerySuccessful {
+        callback_query_id: String,
+    },
+    AnswerCallbackQueryUnsuccessful {
+        callback_query_id: String,
+    },
+    AnswerInlineQuerySuccessful {
+        inline_query_id: String,
+    },
+    AnswerInlineQueryUnsuccessful {
+        inline_query_id: String,
+    },
+    SendPollSuccessful {
+        chat_id: i64,
+        message: Box<Message>,
+    },
+    SendPollUnsuccessful {
+        chat_id: i64,
+        question: String,
+    },
+    StopPollSuccessful {
+        chat_id: i64,
+        message_id: i32,
+        poll: Box<Poll>,
+    },
+    StopPollUnsuccessful {
+        chat_id: i64,
+        message_id: i32,
+    },
+    UnknownCommand {
+        message: Box<Message>,
+        command: ParsedCommand,
+    },
+    Start {
+        message: Box<Message>,
+        payload: String,
+    },
+    DialogueCancelled {
+        chat_id: i64,
+    },
+    DialogueTimedOut {
+        chat_id: i64,
+    },
+    FormCompleted {
+        chat_id: i64,
+        form: String,
+        values: FormValues,
+    },
+    FormCancelled {
+        chat_id: i64,
+        form: String,
+    },
+    AccessDenied {
+        chat_id: Option<i64>,
+        user_id: Option<u64>,
+        reason: AccessDeniedReason,
+        update: Box<Update>,
+    },
+    Throttled {
+        chat_id: Option<i64>,
+        user_id: Option<u64>,
+        scope: ThrottleScope,
+        retry_after: Duration,
+    },
+    BanChatMemberSuccessful {
+        chat_id: i64,
+        user_id: u64,
+    },
+    BanChatMemberUnsuccessful {
+        chat_id: i64,
+        user_id: u64,
+    },
+    UnbanChatMemberSuccessful {
+        chat_id: i64,
+        user_id: u64,
+    },
+    UnbanChatMemberUnsuccessful {
+        chat_id: i64,
+        user_id: u64,
+    },
+    RestrictChatMemberSuccessful {
+        chat_id: i64,
+        user_id: u64,
+    },
+    RestrictChatMemberUnsuccessful {
+        chat_id: i64,
+        user_id: u64,
+    },
+    CaptchaPassed {
+        chat_id: i64,
+        user_id: u64,
+    },
+    CaptchaFailed {
+        chat_id: i64,
+        user_id: u64,
+        failure: CaptchaFailure,
+    },
+    PromoteChatMemberSuccessful {
+        chat_id: i64,
+        user_id: u64,
+    },
+    PromoteChatMemberUnsuccessful {
+        chat_id: i64,
+        user_id: u64,
+    },
+    SetChatAdministratorCustomTitleSuccessful {
+        chat_id: i64,
+        user_id: u64,
+        custom_title: String,
+    },
+    SetChatAdministratorCustomTitleUnsuccessful {
+        chat_id: i64,
+        user_id: u64,
+        custom_title: String,
+    },
 }
 
 impl fmt::Display for BlocState {
@@ -41,13 +264,78 @@ impl fmt::Display for BlocState {
             BlocState::Message { message } => {
                 f.write_str(&format!("Message{{message:{:?}}}", message))
             }
-            BlocState::Command { message } => {
-                f.write_str(&format!("Command{{message:{:?}}}", message))
+            BlocState::Command { message, command } => f.write_str(&format!(
+                "Command{{message:{:?}, command:{:?}}}",
+                message, command
+            )),
+            BlocState::EditedMessage { message } => {
+                f.write_str(&format!("EditedMessage{{message:{:?}}}", message))
+            }
+            BlocState::ChannelPost { message } => {
+                f.write_str(&format!("ChannelPost{{message:{:?}}}", message))
+            }
+            BlocState::EditedChannelPost { message } => {
+                f.write_str(&format!("EditedChannelPost{{message:{:?}}}", message))
+            }
+            BlocState::MyChatMember {
+                chat_id,
+                old_status,
+                new_status,
+                ..
+            } => f.write_str(&format!(
+                "MyChatMember{{chat_id:{}, old_status:{:?}, new_status:{:?}}}",
+                chat_id, old_status, new_status
+            )),
+            BlocState::ChatMember {
+                chat_id,
+                old_status,
+                new_status,
+                chat_member_updated,
+            } => f.write_str(&format!(
+                "ChatMember{{chat_id:{}, user_id:{}, old_status:{:?}, new_status:{:?}}}",
+                chat_id, chat_member_updated.new_chat_member.user.id, old_status, new_status
+            )),
+            BlocState::NewChatMembers {
+                chat_id, members, ..
+            } => f.write_str(&format!(
+                "NewChatMembers{{chat_id:{}, members:{:?}}}",
+                chat_id,
+                members
+                    .iter()
+                    .map(|member| member.id.0)
+                    .collect::<Vec<u64>>()
+            )),
+            BlocState::LeftChatMember {
+                chat_id, member, ..
+            } => f.write_str(&format!(
+                "LeftChatMember{{chat_id:{}, member:{}}}",
+                chat_id, member.id
+            )),
+            BlocState::CallbackQuery { callback_query } => f.write_str(&format!(
+                "CallbackQuery{{callback_query:{:?}}}",
+                callback_query
+            )),
+            BlocState::InlineQuery { inline_query } => {
+                f.write_str(&format!("InlineQuery{{inline_query:{:?}}}", inline_query))
+            }
+            BlocState::ChosenInlineResult {
+                chosen_inline_result,
+            } => f.write_str(&format!(
+                "ChosenInlineResult{{chosen_inline_result:{:?}}}",
+                chosen_inline_result
+            )),
+            BlocState::Poll { poll } => f.write_str(&format!("Poll{{poll:{:?}}}", poll)),
+            BlocState::PollAnswer { poll_answer } => {
+                f.write_str(&format!("PollAnswer{{poll_answer:{:?}}}", poll_answer))
             }
             BlocState::TextToChatSendSuccessful { chat_id, text } => f.write_str(&format!(
                 "TextToChatSendSuccessful{{chat_id:{}, text:{}}}",
                 chat_id, text
             )),
+            BlocState::TextToChatSendUnsuccessful { chat_id, text } => f.write_str(&format!(
+                "TextToChatSendUnsuccessful{{chat_id:{}, text:{}}}",
+                chat_id, text
+            )),
             BlocState::GetFileSuccessful { file_id, file } => f.write_str(&format!(
                 "GetFileSuccessful{{file_id:{}, file: {:?}}}",
                 file_id, file
@@ -58,9 +346,10 @@ impl fmt::Display for BlocState {
             BlocState::DownloadFileSuccessful {
                 file_path,
                 destination_path,
+                sha256,
             } => f.write_str(&format!(
-                "DownloadFileSuccessful{{file_path:{}, destination_path:{}}}",
-                file_path, destination_path
+                "DownloadFileSuccessful{{file_path:{}, destination_path:{}, sha256:{:?}}}",
+                file_path, destination_path, sha256
             )),
             BlocState::DownloadFileUnsuccessful {
                 file_path,
@@ -69,9 +358,221 @@ impl fmt::Display for BlocState {
                 "DownloadFileUnsuccessful{{file_path:{}, destination_path:{}}}",
                 file_path, destination_path
             )),
+            BlocState::FetchFileSuccessful {
+                file_id,
+                file,
+                destination_path,
+                sha256,
+            } => f.write_str(&format!(
+                "FetchFileSuccessful{{file_id:{}, file: {:?}, destination_path:{}, sha256:{:?}}}",
+                file_id, file, destination_path, sha256
+            )),
+            BlocState::FetchFileUnsuccessful {
+                file_id,
+                destination_path,
+            } => f.write_str(&format!(
+                "FetchFileUnsuccessful{{file_id:{}, destination_path:{}}}",
+                file_id, destination_path
+            )),
+            BlocState::FileCacheHit { file_unique_id } => f.write_str(&format!(
+                "FileCacheHit{{file_unique_id:{}}}",
+                file_unique_id
+            )),
+            BlocState::FileCacheMiss { file_unique_id } => f.write_str(&format!(
+                "FileCacheMiss{{file_unique_id:{}}}",
+                file_unique_id
+            )),
+            BlocState::DownloadFileProgress {
+                file_path,
+                received,
+                total,
+            } => f.write_str(&format!(
+                "DownloadFileProgress{{file_path:{}, received:{}, total:{:?}}}",
+                file_path, received, total
+            )),
+            BlocState::DownloadFileToMemorySuccessful { file_path, bytes } => {
+                f.write_str(&format!(
+                    "DownloadFileToMemorySuccessful{{file_path:{}, size:{}}}",
+                    file_path,
+                    bytes.len()
+                ))
+            }
+            BlocState::DownloadFileToMemoryUnsuccessful { file_path } => f.write_str(&format!(
+                "DownloadFileToMemoryUnsuccessful{{file_path:{}}}",
+                file_path
+            )),
+            BlocState::DownloadFileToWriterSuccessful { file_path, size } => f.write_str(&format!(
+                "DownloadFileToWriterSuccessful{{file_path:{}, size:{}}}",
+                file_path, size
+            )),
+            BlocState::DownloadFileToWriterUnsuccessful { file_path } => f.write_str(&format!(
+                "DownloadFileToWriterUnsuccessful{{file_path:{}}}",
+                file_path
+            )),
             BlocState::MediaToChatSendSuccessful { chat_id, .. } => {
                 f.write_str(&format!("MediaToChatSendSuccessful{{chat_id:{}}}", chat_id))
             }
+            BlocState::MediaToChatSendUnsuccessful { chat_id } => f.write_str(&format!(
+                "MediaToChatSendUnsuccessful{{chat_id:{}}}",
+                chat_id
+            )),
+            BlocState::FileToChatSendSuccessful { chat_id, message } => f.write_str(&format!(
+                "FileToChatSendSuccessful{{chat_id:{}, message_id:{}}}",
+                chat_id, message.id
+            )),
+            BlocState::FileToChatSendUnsuccessful { chat_id, file_name } => f.write_str(&format!(
+                "FileToChatSendUnsuccessful{{chat_id:{}, file_name:{}}}",
+                chat_id, file_name
+            )),
+            BlocState::AnswerCallbackQuerySuccessful { callback_query_id } => {
+                f.write_str(&format!(
+                    "AnswerCallbackQuerySuccessful{{callback_query_id:{}}}",
+                    callback_query_id
+                ))
+            }
+            BlocState::AnswerCallbackQueryUnsuccessful { callback_query_id } => {
+                f.write_str(&format!(
+                    "AnswerCallbackQueryUnsuccessful{{callback_query_id:{}}}",
+                    callback_query_id
+                ))
+            }
+            BlocState::AnswerInlineQuerySuccessful { inline_query_id } => f.write_str(&format!(
+                "AnswerInlineQuerySuccessful{{inline_query_id:{}}}",
+                inline_query_id
+            )),
+            BlocState::AnswerInlineQueryUnsuccessful { inline_query_id } => f.write_str(&format!(
+                "AnswerInlineQueryUnsuccessful{{inline_query_id:{}}}",
+                inline_query_id
+            )),
+            BlocState::SendPollSuccessful { chat_id, message } => f.write_str(&format!(
+                "SendPollSuccessful{{chat_id:{}, message_id:{}}}",
+                chat_id, message.id
+            )),
+            BlocState::SendPollUnsuccessful { chat_id, question } => f.write_str(&format!(
+                "SendPollUnsuccessful{{chat_id:{}, question:{}}}",
+                chat_id, question
+            )),
+            BlocState::StopPollSuccessful {
+                chat_id,
+                message_id,
+                poll,
+            } => f.write_str(&format!(
+                "StopPollSuccessful{{chat_id:{}, message_id:{}, poll:{:?}}}",
+                chat_id, message_id, poll
+            )),
+            BlocState::StopPollUnsuccessful {
+                chat_id,
+                message_id,
+            } => f.write_str(&format!(
+                "StopPollUnsuccessful{{chat_id:{}, message_id:{}}}",
+                chat_id, message_id
+            )),
+            BlocState::UnknownCommand { message, command } => f.write_str(&format!(
+                "UnknownCommand{{message:{:?}, command:{:?}}}",
+                message, command
+            )),
+            BlocState::Start { message, payload } => f.write_str(&format!(
+                "Start{{message:{:?}, payload:{}}}",
+                message, payload
+            )),
+            BlocState::DialogueCancelled { chat_id } => {
+                f.write_str(&format!("DialogueCancelled{{chat_id:{}}}", chat_id))
+            }
+            BlocState::DialogueTimedOut { chat_id } => {
+                f.write_str(&format!("DialogueTimedOut{{chat_id:{}}}", chat_id))
+            }
+            BlocState::FormCompleted {
+                chat_id,
+                form,
+                values,
+            } => f.write_str(&format!(
+                "FormCompleted{{chat_id:{}, form:{}, values:{:?}}}",
+                chat_id, form, values
+            )),
+            BlocState::FormCancelled { chat_id, form } => f.write_str(&format!(
+                "FormCancelled{{chat_id:{}, form:{}}}",
+                chat_id, form
+            )),
+            BlocState::AccessDenied {
+                chat_id,
+                user_id,
+                reason,
+                ..
+            } => f.write_str(&format!(
+                "AccessDenied{{chat_id:{:?}, user_id:{:?}, reason:{}}}",
+                chat_id, user_id, reason
+            )),
+            BlocState::Throttled {
+                chat_id,
+                user_id,
+                scope,
+                retry_after,
+            } => f.write_str(&format!(
+                "Throttled{{chat_id:{:?}, user_id:{:?}, scope:{}, retry_after:{:?}}}",
+                chat_id, user_id, scope, retry_after
+            )),
+            BlocState::BanChatMemberSuccessful { chat_id, user_id } => f.write_str(&format!(
+                "BanChatMemberSuccessful{{chat_id:{}, user_id:{}}}",
+                chat_id, user_id
+            )),
+            BlocState::BanChatMemberUnsuccessful { chat_id, user_id } => f.write_str(&format!(
+                "BanChatMemberUnsuccessful{{chat_id:{}, user_id:{}}}",
+                chat_id, user_id
+            )),
+            BlocState::UnbanChatMemberSuccessful { chat_id, user_id } => f.write_str(&format!(
+                "UnbanChatMemberSuccessful{{chat_id:{}, user_id:{}}}",
+                chat_id, user_id
+            )),
+            BlocState::UnbanChatMemberUnsuccessful { chat_id, user_id } => f.write_str(&format!(
+                "UnbanChatMemberUnsuccessful{{chat_id:{}, user_id:{}}}",
+                chat_id, user_id
+            )),
+            BlocState::RestrictChatMemberSuccessful { chat_id, user_id } => f.write_str(&format!(
+                "RestrictChatMemberSuccessful{{chat_id:{}, user_id:{}}}",
+                chat_id, user_id
+            )),
+            BlocState::RestrictChatMemberUnsuccessful { chat_id, user_id } => {
+                f.write_str(&format!(
+                    "RestrictChatMemberUnsuccessful{{chat_id:{}, user_id:{}}}",
+                    chat_id, user_id
+                ))
+            }
+            BlocState::CaptchaPassed { chat_id, user_id } => f.write_str(&format!(
+                "CaptchaPassed{{chat_id:{}, user_id:{}}}",
+                chat_id, user_id
+            )),
+            BlocState::CaptchaFailed {
+                chat_id,
+                user_id,
+                failure,
+            } => f.write_str(&format!(
+                "CaptchaFailed{{chat_id:{}, user_id:{}, failure:{}}}",
+                chat_id, user_id, failure
+            )),
+            BlocState::PromoteChatMemberSuccessful { chat_id, user_id } => f.write_str(&format!(
+                "PromoteChatMemberSuccessful{{chat_id:{}, user_id:{}}}",
+                chat_id, user_id
+            )),
+            BlocState::PromoteChatMemberUnsuccessful { chat_id, user_id } => f.write_str(&format!(
+                "PromoteChatMemberUnsuccessful{{chat_id:{}, user_id:{}}}",
+                chat_id, user_id
+            )),
+            BlocState::SetChatAdministratorCustomTitleSuccessful {
+                chat_id,
+                user_id,
+                custom_title,
+            } => f.write_str(&format!(
+                "SetChatAdministratorCustomTitleSuccessful{{chat_id:{}, user_id:{}, custom_title:{}}}",
+                chat_id, user_id, custom_title
+            )),
+            BlocState::SetChatAdministratorCustomTitleUnsuccessful {
+                chat_id,
+                user_id,
+                custom_title,
+            } => f.write_str(&format!(
+                "SetChatAdministratorCustomTitleUnsuccessful{{chat_id:{}, user_id:{}, custom_title:{}}}",
+                chat_id, user_id, custom_title
+            )),
         }
     }
 }
@@ -29,7 +29,7 @@ mod bot_bloc_test {
                         let event = BlocEvent::TextToChatSend { chat_id, text };
                         let _ = bloc_for_spawn.get_controller().send(event).await;
                     }
-                    BlocState::Command { message } => {
+                    BlocState::Command { message, .. } => {
                         let chat_id = message.chat.id.0;
                         let text = message.text().unwrap().to_string();
 
@@ -57,16 +57,13 @@ mod bot_bloc_test {
         let bloc_for_spawn = bloc_reference_counter.clone();
         tokio::spawn(async move {
             while let Ok(state) = bloc_for_spawn.get_stream().recv().await {
-                match state {
-                    BlocState::Message { message } => {
-                        let chat_id = message.chat.id.0;
-                        let text = message.text().unwrap().to_string();
+                if let BlocState::Message { message } = state {
+                    let chat_id = message.chat.id.0;
+                    let text = message.text().unwrap().to_string();
 
-                        let event = BlocEvent::TextToChatSend { chat_id, text };
+                    let event = BlocEvent::TextToChatSend { chat_id, text };
 
-                        let _ = bloc_for_spawn.get_controller().send(event).await;
-                    }
-                    _ => {}
+                    let _ = bloc_for_spawn.get_controller().send(event).await;
                 }
             }
         });
@@ -114,7 +111,7 @@ mod bot_bloc_with_default_parse_mode_test {
                         let event = BlocEvent::TextToChatSend { chat_id, text };
                         let _ = bloc_for_spawn.get_controller().send(event).await;
                     }
-                    BlocState::Command { message } => {
+                    BlocState::Command { message, .. } => {
                         let chat_id = message.chat.id.0;
                         let text = message.text().unwrap().to_string();
 
@@ -144,16 +141,13 @@ mod bot_bloc_with_default_parse_mode_test {
         let bloc_for_spawn = bloc_reference_counter.clone();
         tokio::spawn(async move {
             while let Ok(state) = bloc_for_spawn.get_stream().recv().await {
-                match state {
-                    BlocState::Message { message } => {
-                        let chat_id = message.chat.id.0;
-                        let text = message.text().unwrap().to_string();
+                if let BlocState::Message { message } = state {
+                    let chat_id = message.chat.id.0;
+                    let text = message.text().unwrap().to_string();
 
-                        let event = BlocEvent::TextToChatSend { chat_id, text };
+                    let event = BlocEvent::TextToChatSend { chat_id, text };
 
-                        let _ = bloc_for_spawn.get_controller().send(event).await;
-                    }
-                    _ => {}
+                    let _ = bloc_for_spawn.get_controller().send(event).await;
                 }
             }
         });
@@ -1,19 +1,43 @@
+use std::future::Future;
+use std::slice;
+use std::sync::Arc;
+
 use async_channel::{Receiver, Sender};
 use async_trait::async_trait;
+use regex::Regex;
 
-use tokio::{fs, signal, task};
+use tokio::{signal, task};
 
 use teloxide::adaptors::AutoSend;
-use teloxide::dispatching::{Dispatcher, UpdateFilterExt};
+use teloxide::dispatching::Dispatcher;
 use teloxide::error_handlers::LoggingErrorHandler;
-use teloxide::net::Download;
-use teloxide::payloads::SendMessageSetters;
+use teloxide::payloads::{
+    SendAnimationSetters, SendAudioSetters, SendDocumentSetters, SendMessageSetters,
+    SendPhotoSetters, SendVideoSetters, SetMyCommandsSetters,
+};
 use teloxide::prelude::{DependencyMap, Request, Requester};
-use teloxide::types::{ChatId, Message, Update};
-use teloxide::{dptree, respond, Bot};
+use teloxide::requests::HasPayload;
+use teloxide::types::{ChatId, Message, UserId};
+use teloxide::{dptree, Bot, RequestError};
 
+use crate::access_control::AccessControl;
 use crate::bloc_event::BlocEvent;
 use crate::bloc_state::BlocState;
+use crate::captcha::Captcha;
+use crate::command_registry::CommandRegistry;
+use crate::dialogue::{Dialogue, Dialogues};
+use crate::download::{
+    check_size, download_to_memory, download_to_path_with_cache, download_to_writer,
+    file_id_from_message, resolve_destination, DownloadOptions,
+};
+use crate::file_cache::{cache_state, FileCache};
+use crate::flood_control::FloodControl;
+use crate::form::Form;
+use crate::media_source::{album_media, MediaKind, MediaSource};
+use crate::middleware::{EventMiddleware, EventMiddlewares, UpdateMiddleware, UpdateMiddlewares};
+use crate::router::{CallbackContext, CommandContext, Router, TextContext};
+use crate::update_handler::{self, prepare_handler};
+use crate::uploaded_files::{with_stored_file_id, UploadKey, UploadedFiles};
 
 use crate::bloc::{BLoC, BotUpdateHandler};
 
@@ -27,6 +51,17 @@ pub struct Bloc {
     event_stream: Receiver<BlocEvent>,
     state_controller: Sender<BlocState>,
     state_stream: Receiver<BlocState>,
+    download_options: DownloadOptions,
+    file_cache: Option<FileCache>,
+    uploaded_files: Option<UploadedFiles>,
+    commands: CommandRegistry,
+    router: Router,
+    dialogues: Dialogues,
+    middlewares: UpdateMiddlewares,
+    event_middlewares: EventMiddlewares,
+    access_control: AccessControl,
+    flood_control: FloodControl,
+    captcha: Option<Captcha>,
 }
 
 impl Bloc {
@@ -40,75 +75,213 @@ impl Bloc {
             event_stream,
             state_controller,
             state_stream,
+            download_options: DownloadOptions::default(),
+            file_cache: None,
+            uploaded_files: None,
+            commands: CommandRegistry::new(),
+            router: Router::new(),
+            dialogues: Dialogues::default(),
+            middlewares: UpdateMiddlewares::default(),
+            event_middlewares: EventMiddlewares::default(),
+            access_control: AccessControl::default(),
+            flood_control: FloodControl::default(),
+            captcha: None,
         }
     }
 
-    pub fn default_update_handler() -> BotUpdateHandler {
-        let message_handler = |message: Message, state_controller: Sender<BlocState>| async move {
-            let state = BlocState::Message {
-                message: Box::new(message),
-            };
+    pub fn with_download_options(mut self, download_options: DownloadOptions) -> Bloc {
+        self.download_options = download_options;
+        self
+    }
 
-            state_controller
-                .send(state)
-                .await
-                .expect("Can't send update state.");
+    pub fn with_file_cache(mut self, file_cache: FileCache) -> Bloc {
+        self.file_cache = Some(file_cache);
+        self
+    }
 
-            respond(())
-        };
+    pub fn with_uploaded_files(mut self, uploaded_files: UploadedFiles) -> Bloc {
+        self.uploaded_files = Some(uploaded_files);
+        self
+    }
 
-        let command_filter =
-            |message: Message| message.text().is_some() && message.text().unwrap().starts_with('/');
+    pub fn with_commands(mut self, commands: CommandRegistry) -> Bloc {
+        self.commands = commands;
+        self
+    }
 
-        let command_handler = |message: Message, state_controller: Sender<BlocState>| async move {
-            let state = BlocState::Command {
-                message: Box::new(message),
-            };
+    pub fn with_router(mut self, router: Router) -> Bloc {
+        self.router = router;
+        self
+    }
 
-            state_controller
-                .send(state)
-                .await
-                .expect("Can't send update state.");
+    pub fn on_command<F, Fut>(mut self, name: impl Into<String>, handler: F) -> Bloc
+    where
+        F: Fn(CommandContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Vec<BlocEvent>> + Send + 'static,
+    {
+        self.router = self.router.on_command(name, handler);
+        self
+    }
 
-            respond(())
-        };
+    pub fn on_text<F, Fut>(mut self, pattern: Regex, handler: F) -> Bloc
+    where
+        F: Fn(TextContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Vec<BlocEvent>> + Send + 'static,
+    {
+        self.router = self.router.on_text(pattern, handler);
+        self
+    }
 
-        dptree::entry().branch(
-            Update::filter_message()
-                .branch(dptree::filter(command_filter).endpoint(command_handler))
-                .branch(dptree::endpoint(message_handler)),
-        )
+    pub fn on_callback<F, Fut>(mut self, prefix: impl Into<String>, handler: F) -> Bloc
+    where
+        F: Fn(CallbackContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Vec<BlocEvent>> + Send + 'static,
+    {
+        self.router = self.router.on_callback(prefix, handler);
+        self
+    }
+
+    pub fn with_dialogue<S: Clone + Send + Sync + 'static>(
+        mut self,
+        dialogue: Dialogue<S>,
+    ) -> Bloc {
+        self.dialogues.push(Arc::new(dialogue));
+        self
+    }
+
+    pub fn with_form(self, form: Form) -> Bloc {
+        self.with_dialogue(form.into_dialogue())
+    }
+
+    /// Middlewares run in the order they were added, before any handler.
+    pub fn with_middleware(mut self, middleware: impl UpdateMiddleware + 'static) -> Bloc {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Event middlewares run in the order they were added, before every
+    /// event reaches the Bot API.
+    pub fn with_event_middleware(mut self, middleware: impl EventMiddleware + 'static) -> Bloc {
+        self.event_middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    pub fn with_access_control(mut self, access_control: AccessControl) -> Bloc {
+        self.access_control = access_control;
+        self
+    }
+
+    pub fn with_flood_control(mut self, flood_control: FloodControl) -> Bloc {
+        self.flood_control = flood_control;
+        self
+    }
+
+    pub fn with_captcha(mut self, captcha: Captcha) -> Bloc {
+        self.captcha = Some(captcha);
+        self
+    }
+
+    pub fn default_update_handler() -> BotUpdateHandler {
+        update_handler::default_update_handler()
+    }
+
+    async fn publish_commands(&self) {
+        if self.commands.is_empty() {
+            return;
+        }
+
+        for (scope, language_code, commands) in self.commands.menus() {
+            let mut request = self.bot.set_my_commands(commands).scope(scope);
+            request.payload_mut().language_code = language_code;
+
+            if let Err(error) = request.await {
+                let log_message = format!("Can't set bot commands. Error: {:?}.", error);
+                log::warn!("{}", log_message);
+            }
+        }
     }
 
     async fn subscribe_on_events(&self) {
         let event_stream = self.event_stream.clone();
         let state_controller = self.state_controller.clone();
         let bot = self.bot.clone();
+        let download_options = self.download_options.clone();
+        let file_cache = self.file_cache.clone();
+        let uploaded_files = self.uploaded_files.clone();
+        let event_middlewares = self.event_middlewares.clone();
+        let mut observer = None;
 
         while let Ok(event) = event_stream.recv().await {
+            // States of the previous event are forwarded before the next one.
+            if let Some(observer) = observer.take() {
+                let _ = observer.await;
+            }
+
+            let event = match event_middlewares.before(event).await {
+                Some(event) => event,
+                None => continue,
+            };
+
+            let (state_controller, event_observer) =
+                event_middlewares.observe(&event, &state_controller);
+            observer = event_observer;
+
             match event {
                 BlocEvent::TextToChatSend { chat_id, text } => {
-                    let _ = bot.send_message(ChatId(chat_id), text.clone()).await;
+                    match bot.send_message(ChatId(chat_id), text.clone()).await {
+                        Ok(_) => {
+                            let state = BlocState::TextToChatSendSuccessful { chat_id, text };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message = format!("Can't send text. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
 
-                    let state = BlocState::TextToChatSendSuccessful { chat_id, text };
-                    let _ = state_controller.send(state).await;
+                            let state = BlocState::TextToChatSendUnsuccessful { chat_id, text };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
                 }
                 BlocEvent::TextToChatSendWithMarkup {
                     chat_id,
                     text,
                     markup,
                 } => {
-                    bot.send_message(ChatId(chat_id), text.clone())
-                        .reply_markup(markup)
-                        .await
-                        .unwrap();
+                    let request = bot
+                        .send_message(ChatId(chat_id), text.clone())
+                        .reply_markup(markup);
 
-                    let state = BlocState::TextToChatSendSuccessful { chat_id, text };
-                    let _ = state_controller.send(state).await;
+                    match request.await {
+                        Ok(_) => {
+                            let state = BlocState::TextToChatSendSuccessful { chat_id, text };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message =
+                                format!("Can't send text with markup. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::TextToChatSendUnsuccessful { chat_id, text };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
                 }
                 BlocEvent::GetFile { file_id } => {
                     match bot.get_file(&file_id).send().await {
                         Ok(file) => {
+                            if let Some(file_cache) = &file_cache {
+                                file_cache
+                                    .remember_file_path(&file.file_path, &file.file_unique_id);
+
+                                let file_unique_id = file.file_unique_id.clone();
+                                let state = if file_cache.contains(&file_unique_id) {
+                                    BlocState::FileCacheHit { file_unique_id }
+                                } else {
+                                    BlocState::FileCacheMiss { file_unique_id }
+                                };
+                                let _ = state_controller.send(state).await;
+                            }
+
                             let state = BlocState::GetFileSuccessful { file_id, file };
                             let _ = state_controller.send(state).await;
                         }
@@ -119,8 +292,6 @@ impl Bloc {
 
                             let state = BlocState::GetFileUnsuccessful { file_id };
                             let _ = state_controller.send(state).await;
-
-                            return;
                         }
                     };
                 }
@@ -128,10 +299,47 @@ impl Bloc {
                     file_path,
                     destination_path,
                 } => {
-                    let mut file = match fs::File::create(&destination_path).await {
-                        Ok(file) => file,
+                    let result = match resolve_destination(&destination_path, &download_options) {
+                        Ok(destination) => {
+                            let on_progress = |received| {
+                                let state = BlocState::DownloadFileProgress {
+                                    file_path: file_path.clone(),
+                                    received,
+                                    total: None,
+                                };
+                                let _ = state_controller.try_send(state);
+                            };
+
+                            download_to_path_with_cache(
+                                &bot,
+                                &file_path,
+                                &destination,
+                                &download_options,
+                                file_cache.as_ref(),
+                                on_progress,
+                            )
+                            .await
+                        }
+                        Err(error) => Err(error),
+                    };
+
+                    match result {
+                        Ok(downloaded_file) => {
+                            if let Some(state) = file_cache.as_ref().and_then(|file_cache| {
+                                cache_state(file_cache, &file_path, downloaded_file.cache_hit)
+                            }) {
+                                let _ = state_controller.send(state).await;
+                            }
+
+                            let state = BlocState::DownloadFileSuccessful {
+                                file_path,
+                                destination_path,
+                                sha256: downloaded_file.sha256,
+                            };
+                            let _ = state_controller.send(state).await;
+                        }
                         Err(error) => {
-                            let log_message = format!("Can't create file. Error: {:?}.", error);
+                            let log_message = format!("Can't download file. Error: {}.", error);
                             log::warn!("{}", log_message);
 
                             let state = BlocState::DownloadFileUnsuccessful {
@@ -139,31 +347,123 @@ impl Bloc {
                                 destination_path,
                             };
                             let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::FetchFile {
+                    file_id,
+                    destination_path,
+                } => {
+                    let file = match bot.get_file(&file_id).send().await {
+                        Ok(file) => file,
+                        Err(error) => {
+                            let log_message =
+                                format!("Can't get file details. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::FetchFileUnsuccessful {
+                                file_id,
+                                destination_path,
+                            };
+                            let _ = state_controller.send(state).await;
 
-                            return;
+                            continue;
                         }
                     };
 
-                    match bot.download_file(&file_path, &mut file).await {
-                        Ok(_) => {
-                            let state = BlocState::DownloadFileSuccessful {
-                                file_path,
+                    if let Some(file_cache) = &file_cache {
+                        file_cache.remember_file_path(&file.file_path, &file.file_unique_id);
+                    }
+
+                    let result = match check_size(file.file_size as usize, &download_options)
+                        .and_then(|_| resolve_destination(&destination_path, &download_options))
+                    {
+                        Ok(destination) => {
+                            let on_progress = |received| {
+                                let state = BlocState::DownloadFileProgress {
+                                    file_path: file.file_path.clone(),
+                                    received,
+                                    total: Some(file.file_size as usize),
+                                };
+                                let _ = state_controller.try_send(state);
+                            };
+
+                            download_to_path_with_cache(
+                                &bot,
+                                &file.file_path,
+                                &destination,
+                                &download_options,
+                                file_cache.as_ref(),
+                                on_progress,
+                            )
+                            .await
+                        }
+                        Err(error) => Err(error),
+                    };
+
+                    match result {
+                        Ok(downloaded_file) => {
+                            if let Some(state) = file_cache.as_ref().and_then(|file_cache| {
+                                cache_state(file_cache, &file.file_path, downloaded_file.cache_hit)
+                            }) {
+                                let _ = state_controller.send(state).await;
+                            }
+
+                            let state = BlocState::FetchFileSuccessful {
+                                file_id,
+                                file,
                                 destination_path,
+                                sha256: downloaded_file.sha256,
                             };
                             let _ = state_controller.send(state).await;
                         }
                         Err(error) => {
-                            let log_message = format!("Can't download file. Error: {:?}.", error);
+                            let log_message = format!("Can't download file. Error: {}.", error);
                             log::warn!("{}", log_message);
 
-                            let state = BlocState::DownloadFileUnsuccessful {
-                                file_path,
+                            let state = BlocState::FetchFileUnsuccessful {
+                                file_id,
                                 destination_path,
                             };
                             let _ = state_controller.send(state).await;
                         }
                     };
                 }
+                BlocEvent::DownloadFileToMemory {
+                    file_path,
+                    max_size,
+                } => {
+                    match download_to_memory(&bot, &file_path, max_size).await {
+                        Ok(bytes) => {
+                            let state =
+                                BlocState::DownloadFileToMemorySuccessful { file_path, bytes };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message = format!("Can't download file. Error: {}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::DownloadFileToMemoryUnsuccessful { file_path };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::DownloadFileToWriter { file_path, writer } => {
+                    match download_to_writer(&bot, &file_path, &writer).await {
+                        Ok(size) => {
+                            let state =
+                                BlocState::DownloadFileToWriterSuccessful { file_path, size };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message = format!("Can't download file. Error: {}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::DownloadFileToWriterUnsuccessful { file_path };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
                 BlocEvent::MediaToChatSend { chat_id, media } => {
                     bot.send_media_group(ChatId(chat_id), media.clone())
                         .await
@@ -171,6 +471,380 @@ impl Bloc {
                     let state = BlocState::MediaToChatSendSuccessful { chat_id, media };
                     let _ = state_controller.send(state).await;
                 }
+                BlocEvent::FileToChatSend {
+                    chat_id,
+                    file,
+                    caption,
+                } => {
+                    let mut upload = match &uploaded_files {
+                        Some(uploaded_files) => uploaded_files.resolve(&file).await,
+                        None => None,
+                    };
+
+                    let file_name = file.get_file_name().to_string();
+                    let caption = caption.unwrap_or_default();
+
+                    let stored_file = with_stored_file_id(file.clone(), upload.as_ref());
+                    let mut result = send_file(&bot, chat_id, stored_file, caption.clone()).await;
+
+                    if let (Err(error), Some(uploaded_files)) = (&result, &uploaded_files) {
+                        if uploaded_files
+                            .forget_stored(slice::from_mut(&mut upload))
+                            .await
+                        {
+                            let log_message = format!(
+                                "Can't send file by stored file id, uploading it again. Error: {:?}.",
+                                error
+                            );
+                            log::warn!("{}", log_message);
+
+                            result = send_file(&bot, chat_id, file, caption).await;
+                        }
+                    }
+
+                    match result {
+                        Ok(message) => {
+                            if let Some(uploaded_files) = &uploaded_files {
+                                uploaded_files
+                                    .remember(upload, file_id_from_message(&message))
+                                    .await;
+                            }
+
+                            let state = BlocState::FileToChatSendSuccessful {
+                                chat_id,
+                                message: Box::new(message),
+                            };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message = format!("Can't send file. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state =
+                                BlocState::FileToChatSendUnsuccessful { chat_id, file_name };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::MediaSourcesToChatSend { chat_id, media } => {
+                    let mut uploads = Vec::new();
+                    for source in &media {
+                        uploads.push(match &uploaded_files {
+                            Some(uploaded_files) => uploaded_files.resolve(source).await,
+                            None => None,
+                        });
+                    }
+
+                    let input_media = |uploads: &[Option<UploadKey>]| {
+                        album_media(media.iter().zip(uploads).map(|(source, upload)| {
+                            with_stored_file_id(source.clone(), upload.as_ref())
+                        }))
+                    };
+
+                    let mut album = input_media(&uploads);
+                    let mut result = bot.send_media_group(ChatId(chat_id), album.clone()).await;
+
+                    if let (Err(error), Some(uploaded_files)) = (&result, &uploaded_files) {
+                        if uploaded_files.forget_stored(&mut uploads).await {
+                            let log_message = format!(
+                                "Can't send media group by stored file ids, uploading them again. Error: {:?}.",
+                                error
+                            );
+                            log::warn!("{}", log_message);
+
+                            album = input_media(&uploads);
+                            result = bot.send_media_group(ChatId(chat_id), album.clone()).await;
+                        }
+                    }
+
+                    match result {
+                        Ok(messages) => {
+                            if let Some(uploaded_files) = &uploaded_files {
+                                for (upload, message) in uploads.into_iter().zip(&messages) {
+                                    uploaded_files
+                                        .remember(upload, file_id_from_message(message))
+                                        .await;
+                                }
+                            }
+
+                            let state = BlocState::MediaToChatSendSuccessful {
+                                chat_id,
+                                media: album,
+                            };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message =
+                                format!("Can't send media group. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::MediaToChatSendUnsuccessful { chat_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::AnswerCallbackQuery {
+                    callback_query_id,
+                    text,
+                    show_alert,
+                    url,
+                    cache_time,
+                } => {
+                    let mut request = bot.answer_callback_query(callback_query_id.clone());
+
+                    let payload = request.payload_mut();
+                    payload.text = text;
+                    payload.show_alert = Some(show_alert);
+                    payload.url = url;
+                    payload.cache_time = cache_time;
+
+                    match request.await {
+                        Ok(_) => {
+                            let state =
+                                BlocState::AnswerCallbackQuerySuccessful { callback_query_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message =
+                                format!("Can't answer callback query. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state =
+                                BlocState::AnswerCallbackQueryUnsuccessful { callback_query_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::AnswerInlineQuery {
+                    inline_query_id,
+                    results,
+                    cache_time,
+                    is_personal,
+                    next_offset,
+                } => {
+                    let mut request = bot.answer_inline_query(inline_query_id.clone(), results);
+
+                    let payload = request.payload_mut();
+                    payload.cache_time = cache_time;
+                    payload.is_personal = Some(is_personal);
+                    payload.next_offset = next_offset;
+
+                    match request.await {
+                        Ok(_) => {
+                            let state = BlocState::AnswerInlineQuerySuccessful { inline_query_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message =
+                                format!("Can't answer inline query. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state =
+                                BlocState::AnswerInlineQueryUnsuccessful { inline_query_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::SendPoll {
+                    chat_id,
+                    question,
+                    options,
+                    poll_type,
+                    is_anonymous,
+                    allows_multiple_answers,
+                    correct_option_id,
+                    explanation,
+                    open_period,
+                } => {
+                    let mut request = bot.send_poll(ChatId(chat_id), question.clone(), options);
+
+                    let payload = request.payload_mut();
+                    payload.type_ = Some(poll_type);
+                    payload.is_anonymous = Some(is_anonymous);
+                    payload.allows_multiple_answers = Some(allows_multiple_answers);
+                    payload.correct_option_id = correct_option_id;
+                    payload.explanation = explanation;
+                    payload.open_period = open_period;
+
+                    match request.await {
+                        Ok(message) => {
+                            let state = BlocState::SendPollSuccessful {
+                                chat_id,
+                                message: Box::new(message),
+                            };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message = format!("Can't send poll. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::SendPollUnsuccessful { chat_id, question };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::StopPoll {
+                    chat_id,
+                    message_id,
+                } => {
+                    match bot.stop_poll(ChatId(chat_id), message_id).await {
+                        Ok(poll) => {
+                            let state = BlocState::StopPollSuccessful {
+                                chat_id,
+                                message_id,
+                                poll: Box::new(poll),
+                            };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message = format!("Can't stop poll. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::StopPollUnsuccessful {
+                                chat_id,
+                                message_id,
+                            };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::BanChatMember {
+                    chat_id,
+                    user_id,
+                    until_date,
+                    revoke_messages,
+                } => {
+                    let mut request = bot.ban_chat_member(ChatId(chat_id), UserId(user_id));
+
+                    let payload = request.payload_mut();
+                    payload.until_date = until_date;
+                    payload.revoke_messages = Some(revoke_messages);
+
+                    match request.await {
+                        Ok(_) => {
+                            let state = BlocState::BanChatMemberSuccessful { chat_id, user_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message = format!("Can't ban chat member. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::BanChatMemberUnsuccessful { chat_id, user_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::UnbanChatMember {
+                    chat_id,
+                    user_id,
+                    only_if_banned,
+                } => {
+                    let mut request = bot.unban_chat_member(ChatId(chat_id), UserId(user_id));
+                    request.payload_mut().only_if_banned = Some(only_if_banned);
+
+                    match request.await {
+                        Ok(_) => {
+                            let state = BlocState::UnbanChatMemberSuccessful { chat_id, user_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message =
+                                format!("Can't unban chat member. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::UnbanChatMemberUnsuccessful { chat_id, user_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::RestrictChatMember {
+                    chat_id,
+                    user_id,
+                    permissions,
+                    until_date,
+                } => {
+                    let mut request =
+                        bot.restrict_chat_member(ChatId(chat_id), UserId(user_id), permissions);
+                    request.payload_mut().until_date = until_date;
+
+                    match request.await {
+                        Ok(_) => {
+                            let state =
+                                BlocState::RestrictChatMemberSuccessful { chat_id, user_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message =
+                                format!("Can't restrict chat member. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state =
+                                BlocState::RestrictChatMemberUnsuccessful { chat_id, user_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::PromoteChatMember {
+                    chat_id,
+                    user_id,
+                    rights,
+                } => {
+                    let mut request = bot.promote_chat_member(ChatId(chat_id), UserId(user_id));
+
+                    rights.apply_to(request.payload_mut());
+
+                    match request.await {
+                        Ok(_) => {
+                            let state = BlocState::PromoteChatMemberSuccessful { chat_id, user_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message =
+                                format!("Can't promote chat member. Error: {:?}.", error);
+                            log::warn!("{}", log_message);
+
+                            let state =
+                                BlocState::PromoteChatMemberUnsuccessful { chat_id, user_id };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
+                BlocEvent::SetChatAdministratorCustomTitle {
+                    chat_id,
+                    user_id,
+                    custom_title,
+                } => {
+                    let request = bot.set_chat_administrator_custom_title(
+                        ChatId(chat_id),
+                        UserId(user_id),
+                        custom_title.clone(),
+                    );
+
+                    match request.await {
+                        Ok(_) => {
+                            let state = BlocState::SetChatAdministratorCustomTitleSuccessful {
+                                chat_id,
+                                user_id,
+                                custom_title,
+                            };
+                            let _ = state_controller.send(state).await;
+                        }
+                        Err(error) => {
+                            let log_message = format!(
+                                "Can't set chat administrator custom title. Error: {:?}.",
+                                error
+                            );
+                            log::warn!("{}", log_message);
+
+                            let state = BlocState::SetChatAdministratorCustomTitleUnsuccessful {
+                                chat_id,
+                                user_id,
+                                custom_title,
+                            };
+                            let _ = state_controller.send(state).await;
+                        }
+                    };
+                }
             }
         }
     }
@@ -191,17 +865,38 @@ impl BLoC<BlocEvent, BlocState> for Bloc {
     }
 
     async fn run_with_handler(&self, handler: BotUpdateHandler) {
+        let handler = prepare_handler(&self.middlewares, handler);
         let that = self.clone();
 
         tokio::spawn(async move {
             let bot = that.bot.clone();
             let state_controller = that.state_controller.clone();
+            let event_controller = that.event_controller.clone();
+            let commands = that.commands.clone();
+            let router = that.router.clone();
+            let dialogues = that.dialogues.clone();
+            let access_control = that.access_control.clone();
+            let flood_control = that.flood_control.clone();
+            let captcha = that.captcha.clone();
+
+            that.publish_commands().await;
+            that.dialogues.spawn_sweeper(that.state_controller.clone());
 
             let dispatch_handler = task::spawn(async move {
                 let ignore_update = |_upd| Box::pin(async {});
 
                 Dispatcher::builder(bot.clone(), handler)
-                    .dependencies(dptree::deps![bot.clone(), state_controller.clone()])
+                    .dependencies(dptree::deps![
+                        bot.clone(),
+                        state_controller.clone(),
+                        event_controller.clone(),
+                        commands.clone(),
+                        router.clone(),
+                        dialogues.clone(),
+                        access_control.clone(),
+                        flood_control.clone(),
+                        captcha.clone()
+                    ])
                     .default_handler(ignore_update)
                     .error_handler(LoggingErrorHandler::with_custom_text(
                         "An error has occurred in the dispatcher",
@@ -239,6 +934,7 @@ impl BLoC<BlocEvent, BlocState> for Bloc {
         webhook: String,
         host: String,
     ) {
+        let handler = prepare_handler(&self.middlewares, handler);
         let that = self.clone();
 
         tokio::spawn(async move {
@@ -247,6 +943,16 @@ impl BLoC<BlocEvent, BlocState> for Bloc {
 
             dependencies.insert(bot.clone());
             dependencies.insert(state_controller.clone());
+            dependencies.insert(that.event_controller.clone());
+            dependencies.insert(that.commands.clone());
+            dependencies.insert(that.router.clone());
+            dependencies.insert(that.dialogues.clone());
+            dependencies.insert(that.access_control.clone());
+            dependencies.insert(that.flood_control.clone());
+            dependencies.insert(that.captcha.clone());
+
+            that.publish_commands().await;
+            that.dialogues.spawn_sweeper(that.state_controller.clone());
 
             let dispatch_handler = task::spawn(async move {
                 let ignore_update = |_upd| Box::pin(async {});
@@ -307,11 +1013,22 @@ impl BLoC<BlocEvent, BlocState> for Bloc {
         cert_path: String,
         key_path: String,
     ) {
+        let handler = prepare_handler(&self.middlewares, handler);
         let bot = self.bot.clone();
         let state_controller = self.state_controller.clone();
 
         dependencies.insert(bot.clone());
         dependencies.insert(state_controller.clone());
+        dependencies.insert(self.event_controller.clone());
+        dependencies.insert(self.commands.clone());
+        dependencies.insert(self.router.clone());
+        dependencies.insert(self.dialogues.clone());
+        dependencies.insert(self.access_control.clone());
+        dependencies.insert(self.flood_control.clone());
+        dependencies.insert(self.captcha.clone());
+
+        self.publish_commands().await;
+        self.dialogues.spawn_sweeper(self.state_controller.clone());
 
         let dispatch_handler = task::spawn(async move {
             let ignore_update = |_upd| Box::pin(async {});
@@ -334,3 +1051,41 @@ impl BLoC<BlocEvent, BlocState> for Bloc {
         let _ = tokio::join!(dispatch_handler, self.subscribe_on_events());
     }
 }
+
+async fn send_file(
+    bot: &AutoSend<Bot>,
+    chat_id: i64,
+    file: MediaSource,
+    caption: String,
+) -> Result<Message, RequestError> {
+    let kind = file.kind();
+    let input_file = file.into_input_file();
+
+    match kind {
+        MediaKind::Photo => {
+            bot.send_photo(ChatId(chat_id), input_file)
+                .caption(caption)
+                .await
+        }
+        MediaKind::Video => {
+            bot.send_video(ChatId(chat_id), input_file)
+                .caption(caption)
+                .await
+        }
+        MediaKind::Animation => {
+            bot.send_animation(ChatId(chat_id), input_file)
+                .caption(caption)
+                .await
+        }
+        MediaKind::Audio => {
+            bot.send_audio(ChatId(chat_id), input_file)
+                .caption(caption)
+                .await
+        }
+        MediaKind::Document => {
+            bot.send_document(ChatId(chat_id), input_file)
+                .caption(caption)
+                .await
+        }
+    }
+}
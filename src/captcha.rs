@@ -0,0 +1,320 @@
+#[cfg(test)]
+#[path = "captcha_test.rs"]
+mod captcha_test;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_channel::Sender;
+
+use teloxide::types::{
+    CallbackQuery, ChatPermissions, InlineKeyboardButton, InlineKeyboardMarkup, ReplyMarkup, User,
+};
+
+use crate::bloc_event::BlocEvent;
+use crate::bloc_state::BlocState;
+
+const CALLBACK_PREFIX: &str = "captcha:";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptchaKind {
+    /// A single button to press.
+    Button,
+    /// A sum of two digits with a few answers to pick from.
+    Math,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptchaFailure {
+    WrongAnswer,
+    TimedOut,
+}
+
+impl fmt::Display for CaptchaFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            CaptchaFailure::WrongAnswer => f.write_str("WrongAnswer"),
+            CaptchaFailure::TimedOut => f.write_str("TimedOut"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Challenge {
+    pub(crate) question: String,
+    pub(crate) answer: String,
+    pub(crate) options: Vec<String>,
+}
+
+#[derive(Debug)]
+struct PendingCaptcha {
+    answer: String,
+    nonce: u64,
+}
+
+/// Restricts new group members until they solve a challenge. Members who
+/// answer wrong or don't answer within the timeout are kicked.
+#[derive(Clone, Debug)]
+pub struct Captcha {
+    kind: CaptchaKind,
+    timeout: Duration,
+    text: String,
+    pending: Arc<Mutex<HashMap<(i64, u64), PendingCaptcha>>>,
+    nonce: Arc<AtomicU64>,
+}
+
+impl Captcha {
+    pub fn new(kind: CaptchaKind) -> Captcha {
+        Captcha {
+            kind,
+            timeout: Duration::from_secs(60),
+            text: "please confirm you are not a bot.".to_string(),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            nonce: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Captcha {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sent after the newcomer's name, followed by the question.
+    pub fn with_text(mut self, text: impl Into<String>) -> Captcha {
+        self.text = text.into();
+        self
+    }
+
+    pub fn is_pending(&self, chat_id: i64, user_id: u64) -> bool {
+        self.pending
+            .lock()
+            .unwrap()
+            .contains_key(&(chat_id, user_id))
+    }
+
+    /// Returns the events restricting and challenging `members` and starts
+    /// their timeouts.
+    pub(crate) fn challenge(
+        &self,
+        chat_id: i64,
+        members: &[User],
+        event_controller: &Sender<BlocEvent>,
+        state_controller: &Sender<BlocState>,
+    ) -> Vec<BlocEvent> {
+        let mut events = Vec::new();
+
+        for member in members.iter().filter(|member| !member.is_bot) {
+            let user_id = member.id.0;
+            let nonce = self.nonce.fetch_add(1, Ordering::Relaxed);
+            let challenge = match self.kind {
+                CaptchaKind::Button => button_challenge(),
+                CaptchaKind::Math => math_challenge(seed(nonce)),
+            };
+
+            self.pending.lock().unwrap().insert(
+                (chat_id, user_id),
+                PendingCaptcha {
+                    answer: challenge.answer.clone(),
+                    nonce,
+                },
+            );
+
+            let buttons = challenge
+                .options
+                .iter()
+                .map(|option| {
+                    InlineKeyboardButton::callback(
+                        option.clone(),
+                        format!("{}{}:{}", CALLBACK_PREFIX, user_id, option),
+                    )
+                })
+                .collect::<Vec<InlineKeyboardButton>>();
+
+            events.push(BlocEvent::RestrictChatMember {
+                chat_id,
+                user_id,
+                permissions: ChatPermissions::empty(),
+                until_date: None,
+            });
+            events.push(BlocEvent::TextToChatSendWithMarkup {
+                chat_id,
+                text: format!(
+                    "{}, {} {}",
+                    member.first_name, self.text, challenge.question
+                )
+                .trim_end()
+                .to_string(),
+                markup: ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup::new(vec![buttons])),
+            });
+
+            self.spawn_timeout(chat_id, user_id, nonce, event_controller, state_controller);
+        }
+
+        events
+    }
+
+    /// Returns the events for a press on a captcha button, `None` when the
+    /// callback query isn't one.
+    pub(crate) async fn answer(
+        &self,
+        callback_query: &CallbackQuery,
+        state_controller: &Sender<BlocState>,
+    ) -> Option<Vec<BlocEvent>> {
+        let (user_id, answer) = callback_query
+            .data
+            .as_deref()?
+            .strip_prefix(CALLBACK_PREFIX)?
+            .split_once(':')?;
+        let user_id = user_id.parse::<u64>().ok()?;
+        let chat_id = callback_query.message.as_ref()?.chat.id.0;
+
+        let reply = |text: &str| BlocEvent::AnswerCallbackQuery {
+            callback_query_id: callback_query.id.clone(),
+            text: Some(text.to_string()),
+            show_alert: false,
+            url: None,
+            cache_time: None,
+        };
+
+        if callback_query.from.id.0 != user_id {
+            return Some(vec![reply("This captcha is for someone else.")]);
+        }
+
+        let pending = self.pending.lock().unwrap().remove(&(chat_id, user_id));
+        let pending = match pending {
+            Some(pending) => pending,
+            None => return Some(vec![reply("This captcha has expired.")]),
+        };
+
+        if pending.answer == answer {
+            let state = BlocState::CaptchaPassed { chat_id, user_id };
+            state_controller
+                .send(state)
+                .await
+                .expect("Can't send captcha state.");
+
+            return Some(vec![
+                reply("Welcome!"),
+                BlocEvent::RestrictChatMember {
+                    chat_id,
+                    user_id,
+                    permissions: ChatPermissions::all(),
+                    until_date: None,
+                },
+            ]);
+        }
+
+        let state = BlocState::CaptchaFailed {
+            chat_id,
+            user_id,
+            failure: CaptchaFailure::WrongAnswer,
+        };
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send captcha state.");
+
+        let mut events = vec![reply("Wrong answer.")];
+        events.extend(kick(chat_id, user_id));
+
+        Some(events)
+    }
+
+    fn spawn_timeout(
+        &self,
+        chat_id: i64,
+        user_id: u64,
+        nonce: u64,
+        event_controller: &Sender<BlocEvent>,
+        state_controller: &Sender<BlocState>,
+    ) {
+        let pending = self.pending.clone();
+        let timeout = self.timeout;
+        let event_controller = event_controller.clone();
+        let state_controller = state_controller.clone();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+
+            // A newer challenge for the same member keeps its own timeout.
+            let timed_out = {
+                let mut pending = pending.lock().unwrap();
+                match pending.get(&(chat_id, user_id)) {
+                    Some(captcha) if captcha.nonce == nonce => {
+                        pending.remove(&(chat_id, user_id));
+                        true
+                    }
+                    _ => false,
+                }
+            };
+
+            if !timed_out {
+                return;
+            }
+
+            for event in kick(chat_id, user_id) {
+                let _ = event_controller.send(event).await;
+            }
+
+            let state = BlocState::CaptchaFailed {
+                chat_id,
+                user_id,
+                failure: CaptchaFailure::TimedOut,
+            };
+            let _ = state_controller.send(state).await;
+        });
+    }
+}
+
+/// Banning and unbanning right away removes the member but lets them join
+/// again.
+fn kick(chat_id: i64, user_id: u64) -> Vec<BlocEvent> {
+    vec![
+        BlocEvent::BanChatMember {
+            chat_id,
+            user_id,
+            until_date: None,
+            revoke_messages: false,
+        },
+        BlocEvent::UnbanChatMember {
+            chat_id,
+            user_id,
+            only_if_banned: true,
+        },
+    ]
+}
+
+fn seed(nonce: u64) -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos() as u64)
+        .unwrap_or_default();
+
+    nanos ^ nonce.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+pub(crate) fn button_challenge() -> Challenge {
+    Challenge {
+        question: String::new(),
+        answer: "I'm not a bot".to_string(),
+        options: vec!["I'm not a bot".to_string()],
+    }
+}
+
+pub(crate) fn math_challenge(seed: u64) -> Challenge {
+    let left = seed % 9 + 1;
+    let right = seed / 9 % 9 + 1;
+    let sum = left + right;
+
+    let mut options = [sum, sum + 1, sum - 1, sum + 2];
+    options.rotate_left((seed / 81 % 4) as usize);
+
+    Challenge {
+        question: format!("How much is {} + {}?", left, right),
+        answer: sum.to_string(),
+        options: options.iter().map(|option| option.to_string()).collect(),
+    }
+}
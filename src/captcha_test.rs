@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use teloxide::types::{CallbackQuery, ChatPermissions};
+
+use crate::bloc_event::BlocEvent;
+use crate::bloc_state::BlocState;
+use crate::test_fixtures::{self, message_json, user};
+
+use super::{math_challenge, Captcha, CaptchaFailure, CaptchaKind};
+
+fn callback_query(user_id: u64, chat_id: i64, data: &str) -> CallbackQuery {
+    let message = message_json(user_id, chat_id, "group", json!({"text": "captcha"}));
+
+    test_fixtures::callback_query(user_id, data, Some(message))
+}
+
+#[test]
+fn math_challenge_offers_the_answer() {
+    for seed in 0..1000 {
+        let challenge = math_challenge(seed);
+
+        assert_eq!(challenge.options.len(), 4);
+        assert!(challenge.options.contains(&challenge.answer));
+    }
+}
+
+#[tokio::test]
+async fn new_member_is_restricted_until_answering() {
+    let (event_controller, _event_stream) = async_channel::unbounded();
+    let (state_controller, state_stream) = async_channel::unbounded();
+    let captcha = Captcha::new(CaptchaKind::Button);
+
+    let events = captcha.challenge(-100, &[user(1)], &event_controller, &state_controller);
+    match events.as_slice() {
+        [BlocEvent::RestrictChatMember { permissions, .. }, BlocEvent::TextToChatSendWithMarkup { text, .. }] =>
+        {
+            assert_eq!(*permissions, ChatPermissions::empty());
+            assert_eq!(text, "User, please confirm you are not a bot.");
+        }
+        _ => panic!("Expected restriction and challenge"),
+    }
+    assert!(captcha.is_pending(-100, 1));
+
+    let data = "captcha:1:I'm not a bot";
+    let events = captcha
+        .answer(&callback_query(2, -100, data), &state_controller)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(captcha.is_pending(-100, 1));
+
+    let events = captcha
+        .answer(&callback_query(1, -100, data), &state_controller)
+        .await
+        .unwrap();
+    assert!(matches!(
+        events.as_slice(),
+        [
+            BlocEvent::AnswerCallbackQuery { .. },
+            BlocEvent::RestrictChatMember { .. }
+        ]
+    ));
+    assert!(!captcha.is_pending(-100, 1));
+    assert!(matches!(
+        state_stream.try_recv(),
+        Ok(BlocState::CaptchaPassed {
+            chat_id: -100,
+            user_id: 1
+        })
+    ));
+
+    let other = callback_query(1, -100, "form:yes");
+    assert!(captcha.answer(&other, &state_controller).await.is_none());
+}
+
+#[tokio::test]
+async fn wrong_answer_kicks() {
+    let (event_controller, _event_stream) = async_channel::unbounded();
+    let (state_controller, state_stream) = async_channel::unbounded();
+    let captcha = Captcha::new(CaptchaKind::Button);
+
+    captcha.challenge(-100, &[user(1)], &event_controller, &state_controller);
+
+    let events = captcha
+        .answer(&callback_query(1, -100, "captcha:1:42"), &state_controller)
+        .await
+        .unwrap();
+    assert!(matches!(
+        events.as_slice(),
+        [
+            BlocEvent::AnswerCallbackQuery { .. },
+            BlocEvent::BanChatMember { .. },
+            BlocEvent::UnbanChatMember { .. }
+        ]
+    ));
+    assert!(matches!(
+        state_stream.try_recv(),
+        Ok(BlocState::CaptchaFailed {
+            failure: CaptchaFailure::WrongAnswer,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn unanswered_captcha_times_out() {
+    let (event_controller, event_stream) = async_channel::unbounded();
+    let (state_controller, state_stream) = async_channel::unbounded();
+    let captcha = Captcha::new(CaptchaKind::Math).with_timeout(Duration::from_millis(10));
+
+    captcha.challenge(-100, &[user(1)], &event_controller, &state_controller);
+
+    assert!(matches!(
+        state_stream.recv().await,
+        Ok(BlocState::CaptchaFailed {
+            failure: CaptchaFailure::TimedOut,
+            ..
+        })
+    ));
+    assert!(matches!(
+        event_stream.try_recv(),
+        Ok(BlocEvent::BanChatMember { .. })
+    ));
+    assert!(!captcha.is_pending(-100, 1));
+}
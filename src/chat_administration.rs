@@ -0,0 +1,72 @@
+#[cfg(test)]
+#[path = "chat_administration_test.rs"]
+mod chat_administration_test;
+
+use teloxide::payloads::PromoteChatMember;
+
+/// Rights granted by `BlocEvent::PromoteChatMember`. Unset rights are left out
+/// of the request and count as not granted, so the default demotes the member.
+/// `can_post_messages` and `can_edit_messages` only apply to channels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PromotionRights {
+    pub is_anonymous: Option<bool>,
+    pub can_manage_chat: Option<bool>,
+    pub can_change_info: Option<bool>,
+    pub can_post_messages: Option<bool>,
+    pub can_edit_messages: Option<bool>,
+    pub can_delete_messages: Option<bool>,
+    pub can_manage_video_chats: Option<bool>,
+    pub can_invite_users: Option<bool>,
+    pub can_restrict_members: Option<bool>,
+    pub can_pin_messages: Option<bool>,
+    pub can_promote_members: Option<bool>,
+}
+
+impl PromotionRights {
+    pub fn demotion() -> PromotionRights {
+        PromotionRights::default()
+    }
+
+    /// Enough to moderate a group without changing it.
+    pub fn moderator() -> PromotionRights {
+        PromotionRights {
+            can_manage_chat: Some(true),
+            can_delete_messages: Some(true),
+            can_restrict_members: Some(true),
+            can_pin_messages: Some(true),
+            ..PromotionRights::default()
+        }
+    }
+
+    pub fn is_demotion(&self) -> bool {
+        [
+            self.is_anonymous,
+            self.can_manage_chat,
+            self.can_change_info,
+            self.can_post_messages,
+            self.can_edit_messages,
+            self.can_delete_messages,
+            self.can_manage_video_chats,
+            self.can_invite_users,
+            self.can_restrict_members,
+            self.can_pin_messages,
+            self.can_promote_members,
+        ]
+        .iter()
+        .all(|right| *right != Some(true))
+    }
+
+    pub(crate) fn apply_to(&self, payload: &mut PromoteChatMember) {
+        payload.is_anonymous = self.is_anonymous;
+        payload.can_manage_chat = self.can_manage_chat;
+        payload.can_change_info = self.can_change_info;
+        payload.can_post_messages = self.can_post_messages;
+        payload.can_edit_messages = self.can_edit_messages;
+        payload.can_delete_messages = self.can_delete_messages;
+        payload.can_manage_video_chats = self.can_manage_video_chats;
+        payload.can_invite_users = self.can_invite_users;
+        payload.can_restrict_members = self.can_restrict_members;
+        payload.can_pin_messages = self.can_pin_messages;
+        payload.can_promote_members = self.can_promote_members;
+    }
+}
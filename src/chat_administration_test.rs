@@ -0,0 +1,29 @@
+use teloxide::payloads::PromoteChatMember;
+use teloxide::types::{ChatId, UserId};
+
+use super::PromotionRights;
+
+#[test]
+fn default_rights_demote() {
+    assert!(PromotionRights::demotion().is_demotion());
+    assert!(PromotionRights {
+        can_pin_messages: Some(false),
+        ..PromotionRights::default()
+    }
+    .is_demotion());
+    assert!(!PromotionRights::moderator().is_demotion());
+}
+
+#[test]
+fn unset_rights_are_left_out_of_the_request() {
+    let mut payload = PromoteChatMember::new(ChatId(-100), UserId(1));
+    PromotionRights::moderator().apply_to(&mut payload);
+
+    assert_eq!(payload.can_restrict_members, Some(true));
+    assert_eq!(payload.is_anonymous, None);
+    assert_eq!(payload.can_post_messages, None);
+    assert_eq!(payload.can_edit_messages, None);
+
+    let request = serde_json::to_value(&payload).unwrap();
+    assert!(request.get("can_post_messages").is_none());
+}
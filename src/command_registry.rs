@@ -0,0 +1,88 @@
+#[cfg(test)]
+#[path = "command_registry_test.rs"]
+mod command_registry_test;
+
+use teloxide::types::{BotCommand, BotCommandScope};
+
+#[derive(Clone, Debug)]
+pub struct RegisteredCommand {
+    pub name: String,
+    pub description: String,
+    pub scope: BotCommandScope,
+    pub language_code: Option<String>,
+}
+
+/// Commands the bot handles. They are published with `set_my_commands` on
+/// startup so the Telegram command menu matches what the bot routes.
+#[derive(Clone, Debug, Default)]
+pub struct CommandRegistry {
+    commands: Vec<RegisteredCommand>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> CommandRegistry {
+        CommandRegistry::default()
+    }
+
+    pub fn command(
+        self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+    ) -> CommandRegistry {
+        self.scoped_command(name, description, BotCommandScope::Default, None)
+    }
+
+    pub fn scoped_command(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        scope: BotCommandScope,
+        language_code: Option<String>,
+    ) -> CommandRegistry {
+        let name = name.into();
+
+        self.commands.push(RegisteredCommand {
+            name: name.trim_start_matches('/').to_lowercase(),
+            description: description.into(),
+            scope,
+            language_code,
+        });
+        self
+    }
+
+    pub fn commands(&self) -> &[RegisteredCommand] {
+        &self.commands
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.commands
+            .iter()
+            .any(|command| command.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Groups the commands into one menu per scope and language.
+    pub(crate) fn menus(&self) -> Vec<(BotCommandScope, Option<String>, Vec<BotCommand>)> {
+        let mut menus: Vec<(BotCommandScope, Option<String>, Vec<BotCommand>)> = Vec::new();
+
+        for command in &self.commands {
+            let bot_command = BotCommand::new(command.name.clone(), command.description.clone());
+
+            match menus.iter_mut().find(|(scope, language_code, _)| {
+                *scope == command.scope && *language_code == command.language_code
+            }) {
+                Some((_, _, bot_commands)) => bot_commands.push(bot_command),
+                None => menus.push((
+                    command.scope.clone(),
+                    command.language_code.clone(),
+                    vec![bot_command],
+                )),
+            }
+        }
+
+        menus
+    }
+}
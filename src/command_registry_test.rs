@@ -0,0 +1,42 @@
+use teloxide::types::BotCommandScope;
+
+use super::CommandRegistry;
+
+#[test]
+fn registered_commands_are_matched_without_case() {
+    let commands = CommandRegistry::new()
+        .command("/start", "Start the bot")
+        .command("help", "Show help");
+
+    assert!(commands.contains("start"));
+    assert!(commands.contains("HELP"));
+    assert!(!commands.contains("settings"));
+}
+
+#[test]
+fn commands_are_grouped_by_scope_and_language() {
+    let commands = CommandRegistry::new()
+        .command("start", "Start the bot")
+        .command("help", "Show help")
+        .scoped_command(
+            "start",
+            "Запустить бота",
+            BotCommandScope::Default,
+            Some("ru".to_string()),
+        )
+        .scoped_command(
+            "ban",
+            "Ban a user",
+            BotCommandScope::AllChatAdministrators,
+            None,
+        );
+
+    let menus = commands.menus();
+
+    assert_eq!(menus.len(), 3);
+    assert_eq!(menus[0].0, BotCommandScope::Default);
+    assert_eq!(menus[0].1, None);
+    assert_eq!(menus[0].2.len(), 2);
+    assert_eq!(menus[1].1, Some("ru".to_string()));
+    assert_eq!(menus[2].0, BotCommandScope::AllChatAdministrators);
+}
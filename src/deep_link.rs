@@ -0,0 +1,85 @@
+#[cfg(test)]
+#[path = "deep_link_test.rs"]
+mod deep_link_test;
+
+use std::fmt;
+
+use url::Url;
+
+/// Telegram accepts at most 64 characters in a `start` parameter.
+pub const MAX_DEEP_LINK_PAYLOAD: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeepLinkError {
+    Empty,
+    TooLong { length: usize },
+    InvalidCharacter { character: char },
+}
+
+impl fmt::Display for DeepLinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            DeepLinkError::Empty => f.write_str("Empty"),
+            DeepLinkError::TooLong { length } => f.write_str(&format!(
+                "TooLong{{length:{}, max_length:{}}}",
+                length, MAX_DEEP_LINK_PAYLOAD
+            )),
+            DeepLinkError::InvalidCharacter { character } => {
+                f.write_str(&format!("InvalidCharacter{{character:{:?}}}", character))
+            }
+        }
+    }
+}
+
+/// `https://t.me/<bot_username>?start=<payload>`, opens a private chat and
+/// sends `/start <payload>`.
+pub fn deep_link(bot_username: &str, payload: &str) -> Result<Url, DeepLinkError> {
+    link(bot_username, "start", payload)
+}
+
+/// `https://t.me/<bot_username>?startgroup=<payload>`, adds the bot to a group.
+pub fn group_deep_link(bot_username: &str, payload: &str) -> Result<Url, DeepLinkError> {
+    link(bot_username, "startgroup", payload)
+}
+
+/// Encodes arbitrary bytes with base64url without padding, which only uses
+/// characters allowed in a payload.
+pub fn encode_payload(data: &[u8]) -> Result<String, DeepLinkError> {
+    let payload = base64::encode_config(data, base64::URL_SAFE_NO_PAD);
+    check_payload(&payload)?;
+
+    Ok(payload)
+}
+
+pub fn decode_payload(payload: &str) -> Option<Vec<u8>> {
+    base64::decode_config(payload, base64::URL_SAFE_NO_PAD).ok()
+}
+
+pub fn check_payload(payload: &str) -> Result<(), DeepLinkError> {
+    if payload.is_empty() {
+        return Err(DeepLinkError::Empty);
+    }
+
+    if payload.len() > MAX_DEEP_LINK_PAYLOAD {
+        return Err(DeepLinkError::TooLong {
+            length: payload.len(),
+        });
+    }
+
+    match payload.chars().find(|character| {
+        !(character.is_ascii_alphanumeric() || *character == '_' || *character == '-')
+    }) {
+        Some(character) => Err(DeepLinkError::InvalidCharacter { character }),
+        None => Ok(()),
+    }
+}
+
+fn link(bot_username: &str, parameter: &str, payload: &str) -> Result<Url, DeepLinkError> {
+    check_payload(payload)?;
+
+    let mut url = Url::parse("https://t.me/").unwrap();
+    url.set_path(bot_username.trim_start_matches('@'));
+    url.query_pairs_mut().append_pair(parameter, payload);
+
+    Ok(url)
+}
@@ -0,0 +1,49 @@
+use super::{
+    check_payload, decode_payload, deep_link, encode_payload, group_deep_link, DeepLinkError,
+};
+
+#[test]
+fn deep_link_contains_start_payload() {
+    let url = deep_link("@OurBot", "ref_42").unwrap();
+
+    assert_eq!(url.as_str(), "https://t.me/OurBot?start=ref_42");
+}
+
+#[test]
+fn group_deep_link_uses_startgroup() {
+    let url = group_deep_link("OurBot", "team-1").unwrap();
+
+    assert_eq!(url.as_str(), "https://t.me/OurBot?startgroup=team-1");
+}
+
+#[test]
+fn payload_is_validated() {
+    assert_eq!(check_payload(""), Err(DeepLinkError::Empty));
+    assert_eq!(
+        check_payload(&"a".repeat(65)),
+        Err(DeepLinkError::TooLong { length: 65 })
+    );
+    assert_eq!(
+        check_payload("ref 42"),
+        Err(DeepLinkError::InvalidCharacter { character: ' ' })
+    );
+    assert!(check_payload(&"a".repeat(64)).is_ok());
+}
+
+#[test]
+fn encoded_payload_round_trips() {
+    let data = "user=42&source=ads?".as_bytes();
+
+    let payload = encode_payload(data).unwrap();
+
+    assert!(check_payload(&payload).is_ok());
+    assert_eq!(decode_payload(&payload), Some(data.to_vec()));
+}
+
+#[test]
+fn encoded_payload_is_limited() {
+    assert_eq!(
+        encode_payload(&[0; 60]),
+        Err(DeepLinkError::TooLong { length: 80 })
+    );
+}
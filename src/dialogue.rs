@@ -0,0 +1,384 @@
+#[cfg(test)]
+#[path = "dialogue_test.rs"]
+mod dialogue_test;
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_channel::Sender;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use tokio::task::JoinHandle;
+
+use teloxide::types::{CallbackQuery, Message};
+
+use crate::bloc_event::BlocEvent;
+use crate::bloc_state::BlocState;
+use crate::dialogue_storage::{DialogueSession, DialogueStorage, InMemoryDialogueStorage};
+use crate::parsed_command::ParsedCommand;
+
+#[derive(Clone, Debug)]
+pub enum DialogueInput {
+    Message(Message),
+    Command {
+        message: Message,
+        command: ParsedCommand,
+    },
+    CallbackQuery(CallbackQuery),
+}
+
+#[derive(Clone, Debug)]
+pub struct DialogueContext<S> {
+    pub chat_id: i64,
+    /// `None` when the start command opens the dialogue.
+    pub state: Option<S>,
+    pub input: DialogueInput,
+}
+
+pub struct DialogueReply<S> {
+    pub next: Option<S>,
+    pub events: Vec<BlocEvent>,
+    pub states: Vec<BlocState>,
+}
+
+impl<S> DialogueReply<S> {
+    pub fn next(state: S, events: Vec<BlocEvent>) -> DialogueReply<S> {
+        DialogueReply {
+            next: Some(state),
+            events,
+            states: Vec::new(),
+        }
+    }
+
+    pub fn exit(events: Vec<BlocEvent>) -> DialogueReply<S> {
+        DialogueReply {
+            next: None,
+            events,
+            states: Vec::new(),
+        }
+    }
+
+    /// Emits `state` on the Bloc stream along with the events.
+    pub fn with_state(mut self, state: BlocState) -> DialogueReply<S> {
+        self.states.push(state);
+        self
+    }
+}
+
+type DialogueHandler<S> =
+    Arc<dyn Fn(DialogueContext<S>) -> BoxFuture<'static, DialogueReply<S>> + Send + Sync>;
+
+type StepMatcher<S> = Arc<dyn Fn(&S) -> bool + Send + Sync>;
+
+/// A multi-step flow per chat. The start command opens it, then every message,
+/// command and callback query of the chat goes to the first step matching the
+/// current state until a handler exits or the cancel command is sent.
+///
+/// Abandoned dialogues are reset by a periodic sweep once the timeout passed,
+/// or on the chat's next update if that comes first. States are kept in memory
+/// unless another storage is set with `with_storage`.
+#[derive(Clone)]
+pub struct Dialogue<S> {
+    start_command: String,
+    cancel_command: String,
+    timeout: Option<Duration>,
+    on_start: DialogueHandler<S>,
+    on_cancel: Option<DialogueHandler<S>>,
+    steps: Vec<(StepMatcher<S>, DialogueHandler<S>)>,
+    storage: Arc<dyn DialogueStorage<S>>,
+}
+
+impl<S: Clone + Send + Sync + 'static> Dialogue<S> {
+    pub fn new<F, Fut>(start_command: impl Into<String>, on_start: F) -> Dialogue<S>
+    where
+        F: Fn(DialogueContext<S>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = DialogueReply<S>> + Send + 'static,
+    {
+        Dialogue {
+            start_command: start_command.into().trim_start_matches('/').to_lowercase(),
+            cancel_command: "cancel".to_string(),
+            timeout: None,
+            on_start: Arc::new(move |context| Box::pin(on_start(context))),
+            on_cancel: None,
+            steps: Vec::new(),
+            storage: Arc::new(InMemoryDialogueStorage::new()),
+        }
+    }
+
+    pub fn step<M, F, Fut>(mut self, matcher: M, handler: F) -> Dialogue<S>
+    where
+        M: Fn(&S) -> bool + Send + Sync + 'static,
+        F: Fn(DialogueContext<S>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = DialogueReply<S>> + Send + 'static,
+    {
+        self.steps.push((
+            Arc::new(matcher),
+            Arc::new(move |context| Box::pin(handler(context))),
+        ));
+        self
+    }
+
+    /// Runs after the cancel command reset the dialogue, `next` is ignored.
+    pub fn on_cancel<F, Fut>(mut self, handler: F) -> Dialogue<S>
+    where
+        F: Fn(DialogueContext<S>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = DialogueReply<S>> + Send + 'static,
+    {
+        self.on_cancel = Some(Arc::new(move |context| Box::pin(handler(context))));
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Dialogue<S> {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_cancel_command(mut self, cancel_command: impl Into<String>) -> Dialogue<S> {
+        self.cancel_command = cancel_command.into().trim_start_matches('/').to_lowercase();
+        self
+    }
+
+    pub fn with_storage(mut self, storage: impl DialogueStorage<S> + 'static) -> Dialogue<S> {
+        self.storage = Arc::new(storage);
+        self
+    }
+
+    pub async fn state(&self, chat_id: i64) -> Option<S> {
+        match self.storage.get(chat_id).await {
+            Ok(session) => session.map(|session| session.state),
+            Err(error) => {
+                let log_message = format!("Can't read dialogue state. Error: {:?}.", error);
+                log::warn!("{}", log_message);
+
+                None
+            }
+        }
+    }
+
+    async fn active_session(
+        &self,
+        chat_id: i64,
+        now: SystemTime,
+    ) -> (Option<DialogueSession<S>>, bool) {
+        let session = match self.storage.get(chat_id).await {
+            Ok(session) => session,
+            Err(error) => {
+                let log_message = format!("Can't read dialogue state. Error: {:?}.", error);
+                log::warn!("{}", log_message);
+
+                None
+            }
+        };
+
+        let expired = match (&session, self.timeout) {
+            (Some(session), Some(timeout)) => now
+                .duration_since(session.updated_at)
+                .map(|elapsed| elapsed > timeout)
+                .unwrap_or(false),
+            _ => false,
+        };
+
+        if expired {
+            self.apply(chat_id, None, now).await;
+            return (None, true);
+        }
+
+        (session, false)
+    }
+
+    async fn apply(&self, chat_id: i64, next: Option<S>, now: SystemTime) {
+        let result = match next {
+            Some(state) => {
+                let session = DialogueSession {
+                    state,
+                    updated_at: now,
+                };
+
+                self.storage.update(chat_id, session).await
+            }
+            None => self.storage.remove(chat_id).await,
+        };
+
+        if let Err(error) = result {
+            let log_message = format!("Can't save dialogue state. Error: {:?}.", error);
+            log::warn!("{}", log_message);
+        }
+    }
+}
+
+#[async_trait]
+pub(crate) trait DialogueRunner: Send + Sync {
+    /// Returns the events to send when the dialogue took the input.
+    async fn handle(
+        &self,
+        chat_id: i64,
+        input: DialogueInput,
+        state_controller: &Sender<BlocState>,
+    ) -> Option<Vec<BlocEvent>>;
+
+    fn timeout(&self) -> Option<Duration>;
+
+    /// Resets the dialogues abandoned for longer than the timeout.
+    async fn sweep(&self, state_controller: &Sender<BlocState>);
+}
+
+#[async_trait]
+impl<S: Clone + Send + Sync + 'static> DialogueRunner for Dialogue<S> {
+    async fn handle(
+        &self,
+        chat_id: i64,
+        input: DialogueInput,
+        state_controller: &Sender<BlocState>,
+    ) -> Option<Vec<BlocEvent>> {
+        let now = SystemTime::now();
+        let (session, timed_out) = self.active_session(chat_id, now).await;
+
+        if timed_out {
+            let _ = state_controller
+                .send(BlocState::DialogueTimedOut { chat_id })
+                .await;
+        }
+
+        let command = match &input {
+            DialogueInput::Command { command, .. } => Some(command.name.to_lowercase()),
+            _ => None,
+        };
+
+        let cancelled = session.is_some() && command.as_ref() == Some(&self.cancel_command);
+
+        let (handler, state) = match session {
+            Some(session) if cancelled => {
+                self.apply(chat_id, None, now).await;
+
+                let _ = state_controller
+                    .send(BlocState::DialogueCancelled { chat_id })
+                    .await;
+
+                match &self.on_cancel {
+                    Some(on_cancel) => (on_cancel.clone(), Some(session.state)),
+                    None => return Some(Vec::new()),
+                }
+            }
+            _ if command.as_ref() == Some(&self.start_command) => (self.on_start.clone(), None),
+            Some(session) => {
+                match self
+                    .steps
+                    .iter()
+                    .find(|(matcher, _)| matcher(&session.state))
+                {
+                    Some((_, handler)) => (handler.clone(), Some(session.state)),
+                    None => {
+                        log::warn!("{}", "Dialogue has no step for the current state.");
+                        self.apply(chat_id, None, now).await;
+
+                        return None;
+                    }
+                }
+            }
+            None => return None,
+        };
+
+        let reply = handler(DialogueContext {
+            chat_id,
+            state,
+            input,
+        })
+        .await;
+
+        if !cancelled {
+            self.apply(chat_id, reply.next, now).await;
+        }
+
+        for state in reply.states {
+            let _ = state_controller.send(state).await;
+        }
+
+        Some(reply.events)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    async fn sweep(&self, state_controller: &Sender<BlocState>) {
+        if self.timeout.is_none() {
+            return;
+        }
+
+        let chat_ids = match self.storage.chat_ids().await {
+            Ok(chat_ids) => chat_ids,
+            Err(error) => {
+                let log_message = format!("Can't list dialogue chats. Error: {:?}.", error);
+                log::warn!("{}", log_message);
+
+                return;
+            }
+        };
+
+        let now = SystemTime::now();
+        for chat_id in chat_ids {
+            if let (_, true) = self.active_session(chat_id, now).await {
+                let _ = state_controller
+                    .send(BlocState::DialogueTimedOut { chat_id })
+                    .await;
+            }
+        }
+    }
+}
+
+/// Dialogues registered on the Bloc, the first one taking an input wins.
+#[derive(Clone, Default)]
+pub(crate) struct Dialogues(Vec<Arc<dyn DialogueRunner>>);
+
+impl Dialogues {
+    pub(crate) fn push(&mut self, dialogue: Arc<dyn DialogueRunner>) {
+        self.0.push(dialogue);
+    }
+
+    pub(crate) async fn handle(
+        &self,
+        chat_id: i64,
+        input: DialogueInput,
+        state_controller: &Sender<BlocState>,
+    ) -> Option<Vec<BlocEvent>> {
+        for dialogue in &self.0 {
+            if let Some(events) = dialogue
+                .handle(chat_id, input.clone(), state_controller)
+                .await
+            {
+                return Some(events);
+            }
+        }
+
+        None
+    }
+
+    /// Sweeps the dialogues every shortest timeout, so an abandoned dialogue
+    /// is reset at most two timeouts after its last update. Zero timeouts
+    /// can't be swept periodically and only expire on the next update.
+    pub(crate) fn spawn_sweeper(
+        &self,
+        state_controller: Sender<BlocState>,
+    ) -> Option<JoinHandle<()>> {
+        let period = self
+            .0
+            .iter()
+            .filter_map(|dialogue| dialogue.timeout())
+            .filter(|timeout| !timeout.is_zero())
+            .min()?;
+        let dialogues = self.clone();
+
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+
+            loop {
+                interval.tick().await;
+
+                for dialogue in &dialogues.0 {
+                    dialogue.sweep(&state_controller).await;
+                }
+            }
+        }))
+    }
+}
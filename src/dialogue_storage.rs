@@ -0,0 +1,216 @@
+#[cfg(test)]
+#[path = "dialogue_storage_test.rs"]
+mod dialogue_storage_test;
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tokio::sync::Mutex as AsyncMutex;
+
+use teloxide::dispatching::dialogue::serializer::Json;
+use teloxide::dispatching::dialogue::{SqliteStorage, SqliteStorageError, Storage};
+use teloxide::types::ChatId;
+
+use crate::uploaded_files::save;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DialogueSession<S> {
+    pub state: S,
+    pub updated_at: SystemTime,
+}
+
+/// Where `Dialogue` keeps the current state of every chat.
+#[async_trait]
+pub trait DialogueStorage<S>: Send + Sync {
+    async fn get(&self, chat_id: i64) -> io::Result<Option<DialogueSession<S>>>;
+    async fn update(&self, chat_id: i64, session: DialogueSession<S>) -> io::Result<()>;
+    async fn remove(&self, chat_id: i64) -> io::Result<()>;
+    /// Chats with a stored session, swept for timed out dialogues.
+    async fn chat_ids(&self) -> io::Result<Vec<i64>>;
+}
+
+/// Loses every dialogue on restart.
+#[derive(Clone)]
+pub struct InMemoryDialogueStorage<S> {
+    sessions: Arc<Mutex<HashMap<i64, DialogueSession<S>>>>,
+}
+
+impl<S> InMemoryDialogueStorage<S> {
+    pub fn new() -> InMemoryDialogueStorage<S> {
+        InMemoryDialogueStorage {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<S> Default for InMemoryDialogueStorage<S> {
+    fn default() -> Self {
+        InMemoryDialogueStorage::new()
+    }
+}
+
+#[async_trait]
+impl<S: Clone + Send + Sync> DialogueStorage<S> for InMemoryDialogueStorage<S> {
+    async fn get(&self, chat_id: i64) -> io::Result<Option<DialogueSession<S>>> {
+        Ok(self.sessions.lock().unwrap().get(&chat_id).cloned())
+    }
+
+    async fn update(&self, chat_id: i64, session: DialogueSession<S>) -> io::Result<()> {
+        self.sessions.lock().unwrap().insert(chat_id, session);
+        Ok(())
+    }
+
+    async fn remove(&self, chat_id: i64) -> io::Result<()> {
+        self.sessions.lock().unwrap().remove(&chat_id);
+        Ok(())
+    }
+
+    async fn chat_ids(&self) -> io::Result<Vec<i64>> {
+        Ok(self.sessions.lock().unwrap().keys().copied().collect())
+    }
+}
+
+/// Keeps every dialogue in one JSON file, rewritten on every change.
+#[derive(Clone)]
+pub struct FileDialogueStorage<S> {
+    storage_path: PathBuf,
+    sessions: Arc<Mutex<HashMap<i64, DialogueSession<S>>>>,
+    write_lock: Arc<AsyncMutex<()>>,
+}
+
+impl<S: DeserializeOwned> FileDialogueStorage<S> {
+    pub fn open(storage_path: impl Into<PathBuf>) -> io::Result<FileDialogueStorage<S>> {
+        let storage_path = storage_path.into();
+
+        let sessions = match std::fs::read(&storage_path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(error) => return Err(error),
+        };
+
+        Ok(FileDialogueStorage {
+            storage_path,
+            sessions: Arc::new(Mutex::new(sessions)),
+            write_lock: Arc::new(AsyncMutex::new(())),
+        })
+    }
+}
+
+impl<S: serde::Serialize> FileDialogueStorage<S> {
+    async fn change(
+        &self,
+        change: impl FnOnce(&mut HashMap<i64, DialogueSession<S>>),
+    ) -> io::Result<()> {
+        // Held until the rename, so snapshots reach the file in the order they
+        // were taken and never share the temporary file.
+        let _write_guard = self.write_lock.lock().await;
+
+        let content = {
+            let mut sessions = self.sessions.lock().unwrap();
+            change(&mut sessions);
+
+            serde_json::to_vec(&*sessions)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+        };
+
+        save(&self.storage_path, &content).await
+    }
+}
+
+#[async_trait]
+impl<S: Clone + serde::Serialize + Send + Sync> DialogueStorage<S> for FileDialogueStorage<S> {
+    async fn get(&self, chat_id: i64) -> io::Result<Option<DialogueSession<S>>> {
+        Ok(self.sessions.lock().unwrap().get(&chat_id).cloned())
+    }
+
+    async fn update(&self, chat_id: i64, session: DialogueSession<S>) -> io::Result<()> {
+        self.change(|sessions| {
+            sessions.insert(chat_id, session);
+        })
+        .await
+    }
+
+    async fn remove(&self, chat_id: i64) -> io::Result<()> {
+        self.change(|sessions| {
+            sessions.remove(&chat_id);
+        })
+        .await
+    }
+
+    async fn chat_ids(&self) -> io::Result<Vec<i64>> {
+        Ok(self.sessions.lock().unwrap().keys().copied().collect())
+    }
+}
+
+/// Keeps dialogues in an SQLite database, serialized as JSON.
+#[derive(Clone)]
+pub struct SqliteDialogueStorage {
+    storage: Arc<SqliteStorage<Json>>,
+    /// Lists the chats, which teloxide's storage can't.
+    pool: SqlitePool,
+}
+
+impl SqliteDialogueStorage {
+    pub async fn open(path: &str) -> io::Result<SqliteDialogueStorage> {
+        let storage = SqliteStorage::open(path, Json).await.map_err(to_io_error)?;
+        let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", path))
+            .await
+            .map_err(to_io_error)?;
+
+        Ok(SqliteDialogueStorage { storage, pool })
+    }
+}
+
+#[async_trait]
+impl<S> DialogueStorage<S> for SqliteDialogueStorage
+where
+    S: serde::Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    async fn get(&self, chat_id: i64) -> io::Result<Option<DialogueSession<S>>> {
+        self.storage
+            .clone()
+            .get_dialogue(ChatId(chat_id))
+            .await
+            .map_err(to_io_error)
+    }
+
+    async fn update(&self, chat_id: i64, session: DialogueSession<S>) -> io::Result<()> {
+        self.storage
+            .clone()
+            .update_dialogue(ChatId(chat_id), session)
+            .await
+            .map_err(to_io_error)
+    }
+
+    async fn remove(&self, chat_id: i64) -> io::Result<()> {
+        let result = <SqliteStorage<Json> as Storage<DialogueSession<S>>>::remove_dialogue(
+            self.storage.clone(),
+            ChatId(chat_id),
+        )
+        .await;
+
+        match result {
+            Ok(()) | Err(SqliteStorageError::DialogueNotFound) => Ok(()),
+            Err(error) => Err(to_io_error(error)),
+        }
+    }
+
+    async fn chat_ids(&self) -> io::Result<Vec<i64>> {
+        sqlx::query_scalar("SELECT chat_id FROM teloxide_dialogues")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(to_io_error)
+    }
+}
+
+fn to_io_error(error: impl ToString) -> io::Error {
+    io::Error::other(error.to_string())
+}
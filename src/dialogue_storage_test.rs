@@ -0,0 +1,126 @@
+use std::time::SystemTime;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::test_fixtures::temporary_path;
+
+use super::{
+    DialogueSession, DialogueStorage, FileDialogueStorage, InMemoryDialogueStorage,
+    SqliteDialogueStorage,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum Form {
+    Name,
+    Age { name: String },
+}
+
+fn session(state: Form) -> DialogueSession<Form> {
+    DialogueSession {
+        state,
+        updated_at: SystemTime::now(),
+    }
+}
+
+async fn check_storage(storage: &dyn DialogueStorage<Form>) {
+    assert!(storage.get(10).await.unwrap().is_none());
+
+    storage.update(10, session(Form::Name)).await.unwrap();
+    storage
+        .update(
+            10,
+            session(Form::Age {
+                name: "Alice".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        storage.get(10).await.unwrap().map(|session| session.state),
+        Some(Form::Age {
+            name: "Alice".to_string()
+        })
+    );
+
+    assert_eq!(storage.chat_ids().await.unwrap(), vec![10]);
+
+    storage.remove(10).await.unwrap();
+    storage.remove(10).await.unwrap();
+
+    assert!(storage.get(10).await.unwrap().is_none());
+    assert!(storage.chat_ids().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn in_memory_storage_keeps_sessions() {
+    check_storage(&InMemoryDialogueStorage::new()).await;
+}
+
+#[tokio::test]
+async fn file_storage_survives_reopening() {
+    let storage_path = temporary_path("dialogues.json");
+
+    let storage = FileDialogueStorage::open(&storage_path).unwrap();
+    check_storage(&storage).await;
+    storage.update(20, session(Form::Name)).await.unwrap();
+
+    let reopened: FileDialogueStorage<Form> = FileDialogueStorage::open(&storage_path).unwrap();
+    let _ = tokio::fs::remove_file(&storage_path).await;
+
+    assert_eq!(
+        reopened.get(20).await.unwrap().map(|session| session.state),
+        Some(Form::Name)
+    );
+}
+
+#[tokio::test]
+async fn file_storage_survives_concurrent_updates() {
+    let storage_path = temporary_path("concurrent_dialogues.json");
+    let storage = FileDialogueStorage::open(&storage_path).unwrap();
+
+    let updates = (0..50).map(|chat_id| {
+        let storage = storage.clone();
+        let state = match chat_id % 2 {
+            0 => Form::Name,
+            _ => Form::Age {
+                name: "Alice".repeat(chat_id as usize),
+            },
+        };
+
+        tokio::spawn(async move { storage.update(chat_id, session(state)).await })
+    });
+    for update in updates.collect::<Vec<_>>() {
+        update.await.unwrap().unwrap();
+    }
+
+    let reopened: FileDialogueStorage<Form> = FileDialogueStorage::open(&storage_path).unwrap();
+    let _ = tokio::fs::remove_file(&storage_path).await;
+
+    for chat_id in 0..50 {
+        assert!(reopened.get(chat_id).await.unwrap().is_some());
+    }
+}
+
+#[tokio::test]
+async fn sqlite_storage_survives_reopening() {
+    let storage_path = temporary_path("dialogues.sqlite");
+    let storage_path = storage_path.to_str().unwrap();
+
+    let storage = SqliteDialogueStorage::open(storage_path).await.unwrap();
+    check_storage(&storage).await;
+    DialogueStorage::<Form>::update(&storage, 20, session(Form::Name))
+        .await
+        .unwrap();
+
+    let reopened = SqliteDialogueStorage::open(storage_path).await.unwrap();
+    let state = DialogueStorage::<Form>::get(&reopened, 20)
+        .await
+        .unwrap()
+        .map(|session| session.state);
+    for suffix in ["", "-shm", "-wal"] {
+        let _ = tokio::fs::remove_file(format!("{}{}", storage_path, suffix)).await;
+    }
+
+    assert_eq!(state, Some(Form::Name));
+}
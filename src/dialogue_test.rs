@@ -0,0 +1,178 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_channel::Receiver;
+
+use crate::bloc_event::BlocEvent;
+use crate::bloc_state::BlocState;
+use crate::test_fixtures::dialogue_input;
+
+use super::{Dialogue, DialogueInput, DialogueReply, DialogueRunner, Dialogues};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Form {
+    Name,
+    Age { name: String },
+}
+
+fn reply(chat_id: i64, text: &str) -> Vec<BlocEvent> {
+    vec![BlocEvent::TextToChatSend {
+        chat_id,
+        text: text.to_string(),
+    }]
+}
+
+fn form() -> Dialogue<Form> {
+    Dialogue::new("register", |context| async move {
+        DialogueReply::next(Form::Name, reply(context.chat_id, "Name?"))
+    })
+    .step(
+        |state| matches!(state, Form::Name),
+        |context| async move {
+            match context.input {
+                DialogueInput::Message(message) => {
+                    let name = message.text().unwrap_or_default().to_string();
+                    DialogueReply::next(Form::Age { name }, reply(context.chat_id, "Age?"))
+                }
+                _ => DialogueReply::next(Form::Name, reply(context.chat_id, "Name?")),
+            }
+        },
+    )
+    .step(
+        |state| matches!(state, Form::Age { .. }),
+        |context| async move { DialogueReply::exit(reply(context.chat_id, "Done")) },
+    )
+}
+
+fn states(state_stream: &Receiver<BlocState>) -> Vec<String> {
+    let mut states = Vec::new();
+
+    while let Ok(state) = state_stream.try_recv() {
+        states.push(state.to_string());
+    }
+
+    states
+}
+
+#[tokio::test]
+async fn dialogue_walks_through_steps() {
+    let (state_controller, _state_stream) = async_channel::unbounded();
+    let dialogue = form();
+
+    assert!(dialogue
+        .handle(10, dialogue_input("hello"), &state_controller)
+        .await
+        .is_none());
+
+    assert!(dialogue
+        .handle(10, dialogue_input("/register"), &state_controller)
+        .await
+        .is_some());
+    assert_eq!(dialogue.state(10).await, Some(Form::Name));
+
+    dialogue
+        .handle(10, dialogue_input("Alice"), &state_controller)
+        .await
+        .unwrap();
+    assert_eq!(
+        dialogue.state(10).await,
+        Some(Form::Age {
+            name: "Alice".to_string()
+        })
+    );
+
+    dialogue
+        .handle(10, dialogue_input("30"), &state_controller)
+        .await
+        .unwrap();
+    assert_eq!(dialogue.state(10).await, None);
+}
+
+#[tokio::test]
+async fn cancel_command_resets_dialogue() {
+    let (state_controller, state_stream) = async_channel::unbounded();
+    let dialogue = form();
+
+    dialogue
+        .handle(10, dialogue_input("/register"), &state_controller)
+        .await
+        .unwrap();
+    dialogue
+        .handle(10, dialogue_input("/cancel"), &state_controller)
+        .await
+        .unwrap();
+
+    assert_eq!(dialogue.state(10).await, None);
+    assert_eq!(states(&state_stream), vec!["DialogueCancelled{chat_id:10}"]);
+
+    assert!(dialogue
+        .handle(10, dialogue_input("/cancel"), &state_controller)
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn abandoned_dialogue_times_out() {
+    let (state_controller, state_stream) = async_channel::unbounded();
+    let dialogue = form().with_timeout(Duration::from_millis(10));
+
+    dialogue
+        .handle(10, dialogue_input("/register"), &state_controller)
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+
+    assert!(dialogue
+        .handle(10, dialogue_input("Alice"), &state_controller)
+        .await
+        .is_none());
+    assert_eq!(dialogue.state(10).await, None);
+    assert_eq!(states(&state_stream), vec!["DialogueTimedOut{chat_id:10}"]);
+}
+
+#[tokio::test]
+async fn sweeper_resets_abandoned_dialogue() {
+    let (state_controller, state_stream) = async_channel::unbounded();
+    let dialogue = form().with_timeout(Duration::from_millis(10));
+
+    let mut dialogues = Dialogues::default();
+    dialogues.push(Arc::new(dialogue.clone()));
+    let sweeper = dialogues.spawn_sweeper(state_controller.clone()).unwrap();
+
+    dialogue
+        .handle(10, dialogue_input("/register"), &state_controller)
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    sweeper.abort();
+
+    assert_eq!(dialogue.state(10).await, None);
+    assert_eq!(states(&state_stream), vec!["DialogueTimedOut{chat_id:10}"]);
+}
+
+#[test]
+fn dialogues_without_timeout_are_not_swept() {
+    let (state_controller, _state_stream) = async_channel::unbounded();
+    let mut dialogues = Dialogues::default();
+    dialogues.push(Arc::new(form()));
+
+    assert!(dialogues.spawn_sweeper(state_controller).is_none());
+}
+
+#[tokio::test]
+async fn zero_timeout_is_not_swept() {
+    let (state_controller, _state_stream) = async_channel::unbounded();
+    let mut dialogues = Dialogues::default();
+    dialogues.push(Arc::new(form().with_timeout(Duration::ZERO)));
+
+    assert!(dialogues.spawn_sweeper(state_controller.clone()).is_none());
+
+    dialogues.push(Arc::new(form().with_timeout(Duration::from_secs(60))));
+    let sweeper = dialogues.spawn_sweeper(state_controller).unwrap();
+
+    tokio::task::yield_now().await;
+    assert!(!sweeper.is_finished());
+    sweeper.abort();
+}
@@ -0,0 +1,449 @@
+#[cfg(test)]
+#[path = "download_test.rs"]
+mod download_test;
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_channel::Sender;
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+
+use tokio::fs;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use teloxide::net::Download;
+use teloxide::types::Message;
+
+use crate::file_cache::FileCache;
+
+/// Caller-provided destination for a streamed download.
+#[derive(Clone)]
+pub enum DownloadWriter {
+    Writer(Arc<Mutex<Box<dyn AsyncWrite + Send + Unpin>>>),
+    Channel(Sender<Vec<u8>>),
+}
+
+impl DownloadWriter {
+    pub fn writer(writer: impl AsyncWrite + Send + Unpin + 'static) -> DownloadWriter {
+        DownloadWriter::Writer(Arc::new(Mutex::new(Box::new(writer))))
+    }
+
+    pub fn channel(sender: Sender<Vec<u8>>) -> DownloadWriter {
+        DownloadWriter::Channel(sender)
+    }
+}
+
+impl fmt::Display for DownloadWriter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            DownloadWriter::Writer(_) => f.write_str("Writer"),
+            DownloadWriter::Channel(_) => f.write_str("Channel"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum DownloadError {
+    TooLarge { max_size: usize },
+    Network(String),
+    Io(std::io::Error),
+    ChannelClosed,
+    OutsideRoot { destination_path: String },
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            DownloadError::TooLarge { max_size } => {
+                f.write_str(&format!("file is larger than {} bytes", max_size))
+            }
+            DownloadError::Network(error) => f.write_str(&format!("network error: {}", error)),
+            DownloadError::Io(error) => f.write_str(&format!("io error: {}", error)),
+            DownloadError::ChannelClosed => f.write_str("channel is closed"),
+            DownloadError::OutsideRoot { destination_path } => f.write_str(&format!(
+                "{} is outside of the download root",
+                destination_path
+            )),
+        }
+    }
+}
+
+pub(crate) async fn download_to_memory<B>(
+    bot: &B,
+    file_path: &str,
+    max_size: usize,
+) -> Result<Vec<u8>, DownloadError>
+where
+    B: for<'w> Download<'w>,
+    for<'w> <B as Download<'w>>::StreamErr: fmt::Debug,
+{
+    let mut stream = Box::pin(bot.download_file_stream(file_path));
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|error| DownloadError::Network(format!("{:?}", error)))?;
+
+        if bytes.len() + chunk.len() > max_size {
+            return Err(DownloadError::TooLarge { max_size });
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
+/// Returns the number of bytes written.
+pub(crate) async fn download_to_writer<B>(
+    bot: &B,
+    file_path: &str,
+    writer: &DownloadWriter,
+) -> Result<usize, DownloadError>
+where
+    B: for<'w> Download<'w>,
+    for<'w> <B as Download<'w>>::StreamErr: fmt::Debug,
+{
+    let mut stream = Box::pin(bot.download_file_stream(file_path));
+    let mut size = 0;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|error| DownloadError::Network(format!("{:?}", error)))?;
+        size += chunk.len();
+
+        match writer {
+            DownloadWriter::Writer(writer) => writer
+                .lock()
+                .await
+                .write_all(&chunk)
+                .await
+                .map_err(DownloadError::Io)?,
+            DownloadWriter::Channel(sender) => sender
+                .send(chunk.to_vec())
+                .await
+                .map_err(|_| DownloadError::ChannelClosed)?,
+        }
+    }
+
+    if let DownloadWriter::Writer(writer) = writer {
+        writer
+            .lock()
+            .await
+            .flush()
+            .await
+            .map_err(DownloadError::Io)?;
+    }
+
+    Ok(size)
+}
+
+/// Limits and checks applied to downloads written to disk.
+#[derive(Clone, Default)]
+pub struct DownloadOptions {
+    pub max_size: Option<usize>,
+    pub checksum: bool,
+    pub root: Option<PathBuf>,
+    pub progress_interval: Option<Duration>,
+}
+
+pub(crate) struct DownloadedFile {
+    pub(crate) sha256: Option<String>,
+    pub(crate) cache_hit: Option<bool>,
+}
+
+/// Rejects destinations escaping the download root, also through symlinks.
+/// Relative destinations are taken relative to the root.
+pub(crate) fn resolve_destination(
+    destination_path: &str,
+    options: &DownloadOptions,
+) -> Result<PathBuf, DownloadError> {
+    let destination = Path::new(destination_path);
+
+    let root = match &options.root {
+        Some(root) => root,
+        None => return Ok(destination.to_path_buf()),
+    };
+
+    let escapes_root = destination
+        .components()
+        .any(|component| component == Component::ParentDir);
+
+    let destination = if destination.is_absolute() {
+        destination.to_path_buf()
+    } else {
+        root.join(destination)
+    };
+
+    let outside_root = || DownloadError::OutsideRoot {
+        destination_path: destination.display().to_string(),
+    };
+
+    if escapes_root || !destination.starts_with(root) {
+        return Err(outside_root());
+    }
+
+    let parent = destination.parent().unwrap_or(root);
+    let canonical_root = canonicalize_existing(root).map_err(DownloadError::Io)?;
+    let canonical_parent = canonicalize_existing(parent).map_err(DownloadError::Io)?;
+
+    if !canonical_parent.starts_with(&canonical_root) {
+        return Err(outside_root());
+    }
+
+    Ok(destination)
+}
+
+/// Resolves symlinks in the part of `path` that exists, directories created
+/// later can't be symlinks yet.
+fn canonicalize_existing(path: &Path) -> std::io::Result<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+
+    loop {
+        match existing.canonicalize() {
+            Ok(canonical) => {
+                return Ok(missing
+                    .iter()
+                    .rev()
+                    .fold(canonical, |path, component| path.join(component)))
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                match (existing.parent(), existing.file_name()) {
+                    (Some(parent), Some(file_name)) => {
+                        missing.push(file_name);
+                        existing = match parent.as_os_str().is_empty() {
+                            true => Path::new("."),
+                            false => parent,
+                        };
+                    }
+                    _ => return Err(error),
+                }
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+pub(crate) fn check_size(size: usize, options: &DownloadOptions) -> Result<(), DownloadError> {
+    match options.max_size {
+        Some(max_size) if size > max_size => Err(DownloadError::TooLarge { max_size }),
+        _ => Ok(()),
+    }
+}
+
+/// Writes into a temporary file next to the destination and renames it on
+/// success, so a failed download never leaves a truncated file behind.
+pub(crate) async fn download_to_path<B>(
+    bot: &B,
+    file_path: &str,
+    destination: &Path,
+    options: &DownloadOptions,
+    on_progress: impl Fn(usize),
+) -> Result<DownloadedFile, DownloadError>
+where
+    B: for<'w> Download<'w>,
+    for<'w> <B as Download<'w>>::StreamErr: fmt::Debug,
+{
+    let mut temporary_path = destination.as_os_str().to_owned();
+    temporary_path.push(".part");
+    let temporary_path = PathBuf::from(temporary_path);
+
+    let result =
+        download_to_temporary_path(bot, file_path, &temporary_path, options, on_progress).await;
+
+    match result {
+        Ok(downloaded_file) => {
+            if let Err(error) = fs::rename(&temporary_path, destination).await {
+                let _ = fs::remove_file(&temporary_path).await;
+                return Err(DownloadError::Io(error));
+            }
+
+            Ok(downloaded_file)
+        }
+        Err(error) => {
+            let _ = fs::remove_file(&temporary_path).await;
+            Err(error)
+        }
+    }
+}
+
+/// Serves the download from the file cache when the file is already there
+/// and stores freshly downloaded files in it otherwise.
+pub(crate) async fn download_to_path_with_cache<B>(
+    bot: &B,
+    file_path: &str,
+    destination: &Path,
+    options: &DownloadOptions,
+    file_cache: Option<&FileCache>,
+    on_progress: impl Fn(usize),
+) -> Result<DownloadedFile, DownloadError>
+where
+    B: for<'w> Download<'w>,
+    for<'w> <B as Download<'w>>::StreamErr: fmt::Debug,
+{
+    let (file_cache, file_unique_id) = match file_cache
+        .and_then(|file_cache| Some((file_cache, file_cache.file_unique_id_for_path(file_path)?)))
+    {
+        Some(cache) => cache,
+        None => return download_to_path(bot, file_path, destination, options, on_progress).await,
+    };
+
+    if let Some(size) = file_cache.entry_size(&file_unique_id) {
+        check_size(size as usize, options)?;
+    }
+
+    if file_cache
+        .copy_to(&file_unique_id, destination)
+        .await
+        .map_err(DownloadError::Io)?
+    {
+        let sha256 = if options.checksum {
+            let bytes = fs::read(destination).await.map_err(DownloadError::Io)?;
+            Some(to_hex(&Sha256::digest(&bytes)))
+        } else {
+            None
+        };
+
+        return Ok(DownloadedFile {
+            sha256,
+            cache_hit: Some(true),
+        });
+    }
+
+    let mut downloaded_file =
+        download_to_path(bot, file_path, destination, options, on_progress).await?;
+
+    if let Err(error) = file_cache.insert(&file_unique_id, destination).await {
+        let log_message = format!("Can't store file in cache. Error: {:?}.", error);
+        log::warn!("{}", log_message);
+    }
+
+    downloaded_file.cache_hit = Some(false);
+    Ok(downloaded_file)
+}
+
+async fn download_to_temporary_path<B>(
+    bot: &B,
+    file_path: &str,
+    temporary_path: &Path,
+    options: &DownloadOptions,
+    on_progress: impl Fn(usize),
+) -> Result<DownloadedFile, DownloadError>
+where
+    B: for<'w> Download<'w>,
+    for<'w> <B as Download<'w>>::StreamErr: fmt::Debug,
+{
+    let mut file = fs::File::create(temporary_path)
+        .await
+        .map_err(DownloadError::Io)?;
+
+    let mut stream = Box::pin(bot.download_file_stream(file_path));
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    let mut progress = Progress::new(options.progress_interval);
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|error| DownloadError::Network(format!("{:?}", error)))?;
+        size += chunk.len();
+        check_size(size, options)?;
+
+        if options.checksum {
+            hasher.update(&chunk);
+        }
+
+        file.write_all(&chunk).await.map_err(DownloadError::Io)?;
+
+        if progress.is_due(Instant::now()) {
+            on_progress(size);
+        }
+    }
+
+    if progress.is_enabled() {
+        on_progress(size);
+    }
+
+    file.sync_all().await.map_err(DownloadError::Io)?;
+
+    let sha256 = if options.checksum {
+        Some(to_hex(&hasher.finalize()))
+    } else {
+        None
+    };
+
+    Ok(DownloadedFile {
+        sha256,
+        cache_hit: None,
+    })
+}
+
+/// Throttles progress reports to at most one per interval.
+pub(crate) struct Progress {
+    interval: Option<Duration>,
+    last_report: Option<Instant>,
+}
+
+impl Progress {
+    pub(crate) fn new(interval: Option<Duration>) -> Progress {
+        Progress {
+            interval,
+            last_report: None,
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.interval.is_some()
+    }
+
+    pub(crate) fn is_due(&mut self, now: Instant) -> bool {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return false,
+        };
+
+        let is_due = match self.last_report {
+            Some(last_report) => now.duration_since(last_report) >= interval,
+            None => true,
+        };
+
+        if is_due {
+            self.last_report = Some(now);
+        }
+
+        is_due
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// File id of the media attached to the message, the largest size for photos.
+pub fn file_id_from_message(message: &Message) -> Option<String> {
+    if let Some(photo) = message.photo() {
+        return photo
+            .iter()
+            .max_by_key(|size| size.width * size.height)
+            .map(|size| size.file_id.clone());
+    }
+
+    message
+        .document()
+        .map(|document| document.file_id.clone())
+        .or_else(|| message.video().map(|video| video.file_id.clone()))
+        .or_else(|| {
+            message
+                .animation()
+                .map(|animation| animation.file_id.clone())
+        })
+        .or_else(|| message.audio().map(|audio| audio.file_id.clone()))
+        .or_else(|| message.voice().map(|voice| voice.file_id.clone()))
+        .or_else(|| {
+            message
+                .video_note()
+                .map(|video_note| video_note.file_id.clone())
+        })
+        .or_else(|| message.sticker().map(|sticker| sticker.file_id.clone()))
+}
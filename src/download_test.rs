@@ -0,0 +1,365 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures::{future, stream};
+use tokio::io::{AsyncReadExt, AsyncWrite};
+
+use serde_json::json;
+
+use teloxide::net::Download;
+
+use crate::file_cache::FileCache;
+use crate::test_fixtures::{message, message_from_text, temporary_path};
+
+use super::{
+    check_size, download_to_memory, download_to_path, download_to_path_with_cache,
+    download_to_writer, file_id_from_message, resolve_destination, to_hex, DownloadError,
+    DownloadOptions, DownloadWriter, Progress,
+};
+
+#[test]
+fn photo_file_id_is_the_largest_size() {
+    let message = message(
+        10,
+        10,
+        "private",
+        json!({"photo": [
+            {"file_id": "small", "file_unique_id": "s", "width": 90, "height": 90},
+            {"file_id": "large", "file_unique_id": "l", "width": 1280, "height": 960},
+            {"file_id": "medium", "file_unique_id": "m", "width": 320, "height": 240}
+        ]}),
+    );
+
+    assert_eq!(file_id_from_message(&message), Some("large".to_string()));
+}
+
+#[test]
+fn document_file_id_is_extracted() {
+    let message = message(
+        10,
+        10,
+        "private",
+        json!({"document": {"file_id": "document", "file_unique_id": "d", "file_name": "report.pdf"}}),
+    );
+
+    assert_eq!(file_id_from_message(&message), Some("document".to_string()));
+}
+
+#[test]
+fn text_message_has_no_file_id() {
+    let message = message_from_text("hello");
+
+    assert_eq!(file_id_from_message(&message), None);
+}
+
+#[test]
+fn destination_without_root_is_kept() {
+    let options = DownloadOptions::default();
+
+    let destination = resolve_destination("../file.png", &options).unwrap();
+    assert_eq!(destination, PathBuf::from("../file.png"));
+}
+
+#[test]
+fn relative_destination_is_joined_to_root() {
+    let options = DownloadOptions {
+        root: Some(PathBuf::from("/srv/downloads")),
+        ..DownloadOptions::default()
+    };
+
+    let destination = resolve_destination("photos/file.png", &options).unwrap();
+    assert_eq!(destination, PathBuf::from("/srv/downloads/photos/file.png"));
+}
+
+#[test]
+fn destination_escaping_root_is_rejected() {
+    let options = DownloadOptions {
+        root: Some(PathBuf::from("/srv/downloads")),
+        ..DownloadOptions::default()
+    };
+
+    assert!(resolve_destination("../etc/passwd", &options).is_err());
+    assert!(resolve_destination("/srv/downloads/../etc/passwd", &options).is_err());
+    assert!(resolve_destination("/tmp/file.png", &options).is_err());
+    assert!(resolve_destination("/srv/downloads/file.png", &options).is_ok());
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_escaping_root_is_rejected() {
+    let root = temporary_path("download_root");
+    let outside = temporary_path("download_outside");
+    std::fs::create_dir_all(root.join("photos")).unwrap();
+    std::fs::create_dir_all(&outside).unwrap();
+    let _ = std::fs::remove_file(root.join("link"));
+    std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+    let options = DownloadOptions {
+        root: Some(root.clone()),
+        ..DownloadOptions::default()
+    };
+
+    let through_link = resolve_destination("link/passwd", &options);
+    let below_link = resolve_destination("link/new/passwd", &options);
+    let inside = resolve_destination("photos/new/file.png", &options);
+    let _ = std::fs::remove_dir_all(&root);
+    let _ = std::fs::remove_dir_all(&outside);
+
+    assert!(matches!(
+        through_link,
+        Err(DownloadError::OutsideRoot { .. })
+    ));
+    assert!(matches!(below_link, Err(DownloadError::OutsideRoot { .. })));
+    assert_eq!(inside.unwrap(), root.join("photos/new/file.png"));
+}
+
+#[test]
+fn size_above_maximum_is_rejected() {
+    let options = DownloadOptions {
+        max_size: Some(10),
+        ..DownloadOptions::default()
+    };
+
+    assert!(check_size(10, &options).is_ok());
+    assert!(check_size(11, &options).is_err());
+    assert!(check_size(usize::MAX, &DownloadOptions::default()).is_ok());
+}
+
+#[test]
+fn hex_is_lowercase_and_padded() {
+    assert_eq!(to_hex(&[0x00, 0x0f, 0xab]), "000fab");
+}
+
+#[test]
+fn progress_is_reported_once_per_interval() {
+    let mut progress = Progress::new(Some(Duration::from_secs(1)));
+    let start = Instant::now();
+
+    assert!(progress.is_due(start));
+    assert!(!progress.is_due(start + Duration::from_millis(500)));
+    assert!(progress.is_due(start + Duration::from_millis(1000)));
+    assert!(!progress.is_due(start + Duration::from_millis(1500)));
+}
+
+#[test]
+fn progress_is_disabled_without_interval() {
+    let mut progress = Progress::new(None);
+
+    assert!(!progress.is_enabled());
+    assert!(!progress.is_due(Instant::now()));
+}
+
+/// Serves `chunks` as the content of every file.
+struct FakeDownload {
+    chunks: Vec<Result<Bytes, String>>,
+}
+
+impl FakeDownload {
+    fn new(chunks: &[&'static [u8]]) -> FakeDownload {
+        FakeDownload {
+            chunks: chunks
+                .iter()
+                .map(|chunk| Ok(Bytes::from_static(chunk)))
+                .collect(),
+        }
+    }
+
+    fn failing(chunks: &[&'static [u8]]) -> FakeDownload {
+        let mut download = FakeDownload::new(chunks);
+        download.chunks.push(Err("connection reset".to_string()));
+        download
+    }
+}
+
+impl<'w> Download<'w> for FakeDownload {
+    type Err = io::Error;
+    type Fut = future::Ready<Result<(), io::Error>>;
+
+    fn download_file(
+        &self,
+        _path: &str,
+        _destination: &'w mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Self::Fut {
+        future::ready(Err(io::Error::other("not supported")))
+    }
+
+    type StreamErr = String;
+    type Stream = stream::Iter<std::vec::IntoIter<Result<Bytes, String>>>;
+
+    fn download_file_stream(&self, _path: &str) -> Self::Stream {
+        stream::iter(self.chunks.clone())
+    }
+}
+
+fn part_path(destination: &Path) -> PathBuf {
+    let mut part_path = destination.as_os_str().to_owned();
+    part_path.push(".part");
+    PathBuf::from(part_path)
+}
+
+#[tokio::test]
+async fn download_is_renamed_into_place_with_checksum() {
+    let destination = temporary_path("download_success");
+    let options = DownloadOptions {
+        checksum: true,
+        ..DownloadOptions::default()
+    };
+
+    let bot = FakeDownload::new(&[b"hello ", b"world"]);
+    let downloaded_file = download_to_path(&bot, "file", &destination, &options, |_| {})
+        .await
+        .unwrap();
+
+    let content = tokio::fs::read(&destination).await.unwrap();
+    let part_exists = part_path(&destination).exists();
+    let _ = tokio::fs::remove_file(&destination).await;
+
+    assert_eq!(content, b"hello world");
+    assert!(!part_exists);
+    assert_eq!(
+        downloaded_file.sha256.as_deref(),
+        Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+    );
+}
+
+#[tokio::test]
+async fn failed_download_leaves_nothing_behind() {
+    let destination = temporary_path("download_failure");
+
+    let bot = FakeDownload::failing(&[b"hello "]);
+    let result = download_to_path(
+        &bot,
+        "file",
+        &destination,
+        &DownloadOptions::default(),
+        |_| {},
+    )
+    .await;
+
+    assert!(matches!(result, Err(DownloadError::Network(_))));
+    assert!(!destination.exists());
+    assert!(!part_path(&destination).exists());
+}
+
+#[tokio::test]
+async fn oversized_download_is_cut_off() {
+    let destination = temporary_path("download_oversized");
+    let options = DownloadOptions {
+        max_size: Some(8),
+        ..DownloadOptions::default()
+    };
+
+    let bot = FakeDownload::new(&[b"hello ", b"world"]);
+    let result = download_to_path(&bot, "file", &destination, &options, |_| {}).await;
+
+    assert!(matches!(
+        result,
+        Err(DownloadError::TooLarge { max_size: 8 })
+    ));
+    assert!(!destination.exists());
+    assert!(!part_path(&destination).exists());
+}
+
+#[tokio::test]
+async fn cached_file_respects_max_size() {
+    let directory = temporary_path("download_cache");
+    let source = temporary_path("download_cache_source");
+    let destination = temporary_path("download_cache_destination");
+    tokio::fs::write(&source, b"hello world").await.unwrap();
+
+    let file_cache = FileCache::open(&directory, 1024).unwrap();
+    file_cache.remember_file_path("file", "AgADBAAD");
+    file_cache.insert("AgADBAAD", &source).await.unwrap();
+
+    let options = DownloadOptions {
+        max_size: Some(8),
+        ..DownloadOptions::default()
+    };
+    let bot = FakeDownload::new(&[]);
+    let result = download_to_path_with_cache(
+        &bot,
+        "file",
+        &destination,
+        &options,
+        Some(&file_cache),
+        |_| {},
+    )
+    .await;
+
+    let destination_exists = destination.exists();
+    let _ = tokio::fs::remove_dir_all(&directory).await;
+    let _ = tokio::fs::remove_file(&source).await;
+
+    assert!(matches!(
+        result,
+        Err(DownloadError::TooLarge { max_size: 8 })
+    ));
+    assert!(!destination_exists);
+}
+
+#[tokio::test]
+async fn download_to_memory_respects_max_size() {
+    let bot = FakeDownload::new(&[b"hello ", b"world"]);
+
+    assert_eq!(
+        download_to_memory(&bot, "file", 11).await.unwrap(),
+        b"hello world"
+    );
+    assert!(matches!(
+        download_to_memory(&bot, "file", 10).await,
+        Err(DownloadError::TooLarge { max_size: 10 })
+    ));
+}
+
+#[tokio::test]
+async fn download_to_writer_writes_everything() {
+    let (writer, mut reader) = tokio::io::duplex(64);
+    let bot = FakeDownload::new(&[b"hello ", b"world"]);
+
+    let size = download_to_writer(&bot, "file", &DownloadWriter::writer(writer))
+        .await
+        .unwrap();
+
+    let mut content = vec![0; size];
+    reader.read_exact(&mut content).await.unwrap();
+
+    assert_eq!(size, 11);
+    assert_eq!(content, b"hello world");
+}
+
+#[tokio::test]
+async fn download_to_channel_sends_chunks() {
+    let (sender, receiver) = async_channel::unbounded();
+    let bot = FakeDownload::new(&[b"hello ", b"world"]);
+
+    let size = download_to_writer(&bot, "file", &DownloadWriter::channel(sender))
+        .await
+        .unwrap();
+
+    assert_eq!(size, 11);
+    assert_eq!(receiver.recv().await.unwrap(), b"hello ");
+    assert_eq!(receiver.recv().await.unwrap(), b"world");
+}
+
+#[tokio::test]
+async fn closed_channel_stops_download() {
+    let (sender, receiver) = async_channel::unbounded();
+    drop(receiver);
+    let bot = FakeDownload::new(&[b"hello ", b"world"]);
+
+    let result = download_to_writer(&bot, "file", &DownloadWriter::channel(sender)).await;
+
+    assert!(matches!(result, Err(DownloadError::ChannelClosed)));
+}
+
+#[tokio::test]
+async fn failed_stream_is_reported_to_writer_caller() {
+    let (sender, _receiver) = async_channel::unbounded();
+    let bot = FakeDownload::failing(&[b"hello "]);
+
+    let result = download_to_writer(&bot, "file", &DownloadWriter::channel(sender)).await;
+
+    assert!(matches!(result, Err(DownloadError::Network(_))));
+}
@@ -0,0 +1,313 @@
+#[cfg(test)]
+#[path = "file_cache_test.rs"]
+mod file_cache_test;
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::fs;
+
+use crate::bloc_state::BlocState;
+
+/// Mappings from file paths to files not in the cache are forgotten beyond this.
+const MAX_FILE_PATHS: usize = 10_000;
+
+/// On-disk cache of downloaded files keyed by `file_unique_id`, evicting the
+/// least recently used files once `max_size` bytes are exceeded.
+#[derive(Clone)]
+pub struct FileCache {
+    directory: PathBuf,
+    index: Arc<Mutex<FileCacheIndex>>,
+}
+
+impl FileCache {
+    /// Creates the directory if needed and indexes files left by a previous run.
+    pub fn open(directory: impl Into<PathBuf>, max_size: u64) -> io::Result<FileCache> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&directory)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let file_unique_id = entry.file_name().to_string_lossy().to_string();
+
+            // Left by a copy interrupted in a previous run.
+            if metadata.is_file() && file_unique_id.ends_with(".part") {
+                let _ = std::fs::remove_file(entry.path());
+                continue;
+            }
+
+            if metadata.is_file() && is_valid_file_unique_id(&file_unique_id) {
+                files.push((metadata.modified()?, file_unique_id, metadata.len()));
+            }
+        }
+        files.sort();
+
+        let mut index = FileCacheIndex::new(max_size);
+        for (_, file_unique_id, size) in files {
+            for evicted in index.insert(&file_unique_id, size) {
+                let _ = std::fs::remove_file(directory.join(evicted));
+            }
+        }
+
+        Ok(FileCache {
+            directory,
+            index: Arc::new(Mutex::new(index)),
+        })
+    }
+
+    pub(crate) fn remember_file_path(&self, file_path: &str, file_unique_id: &str) {
+        self.index
+            .lock()
+            .unwrap()
+            .remember_file_path(file_path, file_unique_id);
+    }
+
+    pub(crate) fn file_unique_id_for_path(&self, file_path: &str) -> Option<String> {
+        self.index
+            .lock()
+            .unwrap()
+            .file_unique_id_for_path(file_path)
+    }
+
+    pub fn contains(&self, file_unique_id: &str) -> bool {
+        self.index.lock().unwrap().contains(file_unique_id)
+    }
+
+    pub fn size(&self) -> u64 {
+        self.index.lock().unwrap().size()
+    }
+
+    pub(crate) fn entry_size(&self, file_unique_id: &str) -> Option<u64> {
+        self.index.lock().unwrap().entry_size(file_unique_id)
+    }
+
+    /// Copies the cached file to the destination. Returns `false` on a miss.
+    pub(crate) async fn copy_to(
+        &self,
+        file_unique_id: &str,
+        destination: &Path,
+    ) -> io::Result<bool> {
+        if !self.index.lock().unwrap().touch(file_unique_id) {
+            return Ok(false);
+        }
+
+        let mut temporary_path = destination.as_os_str().to_owned();
+        temporary_path.push(".part");
+
+        let result = async {
+            fs::copy(self.path(file_unique_id), &temporary_path).await?;
+            fs::rename(&temporary_path, destination).await
+        }
+        .await;
+
+        match result {
+            Ok(()) => Ok(true),
+            Err(error) => {
+                let _ = fs::remove_file(&temporary_path).await;
+
+                if error.kind() == io::ErrorKind::NotFound {
+                    self.index.lock().unwrap().remove(file_unique_id);
+                    return Ok(false);
+                }
+
+                Err(error)
+            }
+        }
+    }
+
+    pub(crate) async fn insert(&self, file_unique_id: &str, source: &Path) -> io::Result<()> {
+        if !is_valid_file_unique_id(file_unique_id) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid file unique id: {}", file_unique_id),
+            ));
+        }
+
+        let path = self.path(file_unique_id);
+        let mut temporary_path = path.as_os_str().to_owned();
+        temporary_path.push(".part");
+
+        let result = async {
+            let size = fs::copy(source, &temporary_path).await?;
+
+            if !self.index.lock().unwrap().fits(size) {
+                fs::remove_file(&temporary_path).await?;
+                return Ok(None);
+            }
+
+            fs::rename(&temporary_path, &path).await?;
+
+            Ok(Some(size))
+        }
+        .await;
+
+        let size = match result {
+            Ok(Some(size)) => size,
+            // Larger than the whole cache, so it is not cached at all.
+            Ok(None) => return Ok(()),
+            Err(error) => {
+                let _ = fs::remove_file(&temporary_path).await;
+                return Err(error);
+            }
+        };
+
+        let evicted = self.index.lock().unwrap().insert(file_unique_id, size);
+
+        for file_unique_id in evicted {
+            let _ = fs::remove_file(self.path(&file_unique_id)).await;
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, file_unique_id: &str) -> PathBuf {
+        self.directory.join(file_unique_id)
+    }
+}
+
+pub(crate) fn cache_state(
+    file_cache: &FileCache,
+    file_path: &str,
+    cache_hit: Option<bool>,
+) -> Option<BlocState> {
+    let file_unique_id = file_cache.file_unique_id_for_path(file_path)?;
+
+    match cache_hit? {
+        true => Some(BlocState::FileCacheHit { file_unique_id }),
+        false => Some(BlocState::FileCacheMiss { file_unique_id }),
+    }
+}
+
+pub(crate) fn is_valid_file_unique_id(file_unique_id: &str) -> bool {
+    !file_unique_id.is_empty()
+        && file_unique_id
+            .chars()
+            .all(|symbol| symbol.is_ascii_alphanumeric() || symbol == '-' || symbol == '_')
+}
+
+struct FileCacheEntry {
+    size: u64,
+    last_access: u64,
+}
+
+pub(crate) struct FileCacheIndex {
+    max_size: u64,
+    size: u64,
+    clock: u64,
+    entries: HashMap<String, FileCacheEntry>,
+    file_paths: HashMap<String, String>,
+}
+
+impl FileCacheIndex {
+    pub(crate) fn new(max_size: u64) -> FileCacheIndex {
+        FileCacheIndex {
+            max_size,
+            size: 0,
+            clock: 0,
+            entries: HashMap::new(),
+            file_paths: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn remember_file_path(&mut self, file_path: &str, file_unique_id: &str) {
+        if self.file_paths.len() >= MAX_FILE_PATHS {
+            let entries = &self.entries;
+            self.file_paths
+                .retain(|_, file_unique_id| entries.contains_key(file_unique_id));
+        }
+
+        self.file_paths
+            .insert(file_path.to_string(), file_unique_id.to_string());
+    }
+
+    pub(crate) fn file_unique_id_for_path(&self, file_path: &str) -> Option<String> {
+        self.file_paths.get(file_path).cloned()
+    }
+
+    pub(crate) fn contains(&self, file_unique_id: &str) -> bool {
+        self.entries.contains_key(file_unique_id)
+    }
+
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub(crate) fn entry_size(&self, file_unique_id: &str) -> Option<u64> {
+        self.entries.get(file_unique_id).map(|entry| entry.size)
+    }
+
+    /// Marks the entry as recently used. Returns `false` if it is not cached.
+    pub(crate) fn touch(&mut self, file_unique_id: &str) -> bool {
+        self.clock += 1;
+
+        match self.entries.get_mut(file_unique_id) {
+            Some(entry) => {
+                entry.last_access = self.clock;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn fits(&self, size: u64) -> bool {
+        size <= self.max_size
+    }
+
+    /// Returns the entries evicted to stay within `max_size`. An entry larger
+    /// than the whole cache is evicted itself instead of the others.
+    pub(crate) fn insert(&mut self, file_unique_id: &str, size: u64) -> Vec<String> {
+        if !self.fits(size) {
+            self.remove(file_unique_id);
+            return vec![file_unique_id.to_string()];
+        }
+
+        self.remove_entry(file_unique_id);
+        self.clock += 1;
+
+        self.entries.insert(
+            file_unique_id.to_string(),
+            FileCacheEntry {
+                size,
+                last_access: self.clock,
+            },
+        );
+        self.size += size;
+
+        let mut evicted = Vec::new();
+        while self.size > self.max_size {
+            let least_recently_used = self
+                .entries
+                .iter()
+                .filter(|(key, _)| key.as_str() != file_unique_id)
+                .min_by_key(|(_, entry)| entry.last_access)
+                .map(|(key, _)| key.clone());
+
+            match least_recently_used {
+                Some(key) => {
+                    self.remove(&key);
+                    evicted.push(key);
+                }
+                None => break,
+            }
+        }
+
+        evicted
+    }
+
+    /// Drops the entry and the file paths mapped to it.
+    pub(crate) fn remove(&mut self, file_unique_id: &str) {
+        self.remove_entry(file_unique_id);
+        self.file_paths
+            .retain(|_, mapped_file_unique_id| mapped_file_unique_id != file_unique_id);
+    }
+
+    fn remove_entry(&mut self, file_unique_id: &str) {
+        if let Some(entry) = self.entries.remove(file_unique_id) {
+            self.size -= entry.size;
+        }
+    }
+}
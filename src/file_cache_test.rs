@@ -0,0 +1,132 @@
+use crate::test_fixtures::temporary_path;
+
+use super::{is_valid_file_unique_id, FileCache, FileCacheIndex, MAX_FILE_PATHS};
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let mut index = FileCacheIndex::new(100);
+
+    assert!(index.insert("first", 40).is_empty());
+    assert!(index.insert("second", 40).is_empty());
+    assert!(index.touch("first"));
+
+    let evicted = index.insert("third", 40);
+
+    assert_eq!(evicted, vec!["second".to_string()]);
+    assert!(index.contains("first"));
+    assert!(index.contains("third"));
+    assert_eq!(index.size(), 80);
+}
+
+#[test]
+fn entry_larger_than_cache_is_not_kept() {
+    let mut index = FileCacheIndex::new(100);
+
+    index.insert("first", 40);
+    let evicted = index.insert("huge", 150);
+
+    assert_eq!(evicted, vec!["huge".to_string()]);
+    assert!(index.contains("first"));
+    assert!(!index.contains("huge"));
+    assert_eq!(index.size(), 40);
+}
+
+#[test]
+fn reinserted_entry_is_not_counted_twice() {
+    let mut index = FileCacheIndex::new(100);
+
+    index.insert("first", 40);
+    index.insert("first", 60);
+
+    assert_eq!(index.size(), 60);
+}
+
+#[test]
+fn file_path_is_mapped_to_file_unique_id() {
+    let mut index = FileCacheIndex::new(100);
+
+    index.remember_file_path("photos/file_1.jpg", "AgADBAAD");
+
+    assert_eq!(
+        index.file_unique_id_for_path("photos/file_1.jpg"),
+        Some("AgADBAAD".to_string())
+    );
+    assert_eq!(index.file_unique_id_for_path("photos/file_2.jpg"), None);
+}
+
+#[test]
+fn evicted_entries_forget_their_file_paths() {
+    let mut index = FileCacheIndex::new(100);
+
+    index.remember_file_path("photos/file_1.jpg", "first");
+    index.insert("first", 60);
+    index.remember_file_path("photos/file_2.jpg", "second");
+    index.insert("second", 60);
+
+    assert_eq!(index.file_unique_id_for_path("photos/file_1.jpg"), None);
+    assert_eq!(
+        index.file_unique_id_for_path("photos/file_2.jpg"),
+        Some("second".to_string())
+    );
+}
+
+#[test]
+fn uncached_file_paths_are_capped() {
+    let mut index = FileCacheIndex::new(100);
+
+    index.remember_file_path("photos/cached.jpg", "cached");
+    index.insert("cached", 10);
+    for number in 0..MAX_FILE_PATHS {
+        index.remember_file_path(&format!("photos/file_{}.jpg", number), "uncached");
+    }
+
+    assert!(index.file_paths.len() <= MAX_FILE_PATHS);
+    assert_eq!(
+        index.file_unique_id_for_path("photos/cached.jpg"),
+        Some("cached".to_string())
+    );
+}
+
+#[test]
+fn file_unique_id_must_be_a_plain_file_name() {
+    assert!(is_valid_file_unique_id("AgADBAAD-_x"));
+    assert!(!is_valid_file_unique_id("../secret"));
+    assert!(!is_valid_file_unique_id(""));
+}
+
+#[tokio::test]
+async fn interrupted_copies_are_not_indexed() {
+    let directory = temporary_path("file_cache");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("AgADBAAD.part"), b"trunc").unwrap();
+
+    let file_cache = FileCache::open(&directory, 100).unwrap();
+    let part_exists = directory.join("AgADBAAD.part").exists();
+    let _ = std::fs::remove_dir_all(&directory);
+
+    assert!(!file_cache.contains("AgADBAAD"));
+    assert!(!part_exists);
+}
+
+#[tokio::test]
+async fn file_larger_than_cache_is_not_cached() {
+    let directory = temporary_path("file_cache_oversized");
+    let small = temporary_path("file_cache_small");
+    let huge = temporary_path("file_cache_huge");
+    std::fs::write(&small, vec![0; 40]).unwrap();
+    std::fs::write(&huge, vec![0; 150]).unwrap();
+
+    let file_cache = FileCache::open(&directory, 100).unwrap();
+    file_cache.insert("AgADsmall", &small).await.unwrap();
+    file_cache.insert("AgADhuge", &huge).await.unwrap();
+
+    let cached_files = std::fs::read_dir(&directory).unwrap().count();
+    let _ = std::fs::remove_dir_all(&directory);
+    let _ = std::fs::remove_file(&small);
+    let _ = std::fs::remove_file(&huge);
+
+    assert!(file_cache.contains("AgADsmall"));
+    assert!(!file_cache.contains("AgADhuge"));
+    assert_eq!(file_cache.size(), 40);
+    assert_eq!(cached_files, 1);
+}
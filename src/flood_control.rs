@@ -0,0 +1,195 @@
+#[cfg(test)]
+#[path = "flood_control_test.rs"]
+mod flood_control_test;
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_updates: usize,
+    pub window: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ThrottleScope {
+    User,
+    Chat,
+}
+
+impl fmt::Display for ThrottleScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            ThrottleScope::User => f.write_str("User"),
+            ThrottleScope::Chat => f.write_str("Chat"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Throttled {
+    pub scope: ThrottleScope,
+    pub retry_after: Duration,
+    /// Whether this is the first update dropped since the limit was hit.
+    pub first: bool,
+}
+
+#[derive(Debug, Default)]
+struct FloodEntry {
+    hits: VecDeque<Instant>,
+    throttled: bool,
+}
+
+#[derive(Debug, Default)]
+struct FloodState {
+    users: HashMap<u64, FloodEntry>,
+    chats: HashMap<i64, FloodEntry>,
+    last_sweep: Option<Instant>,
+}
+
+/// Drops updates from users and chats sending more than the configured
+/// number of updates within a sliding window. Dropped updates don't count
+/// towards the limit.
+#[derive(Clone, Debug, Default)]
+pub struct FloodControl {
+    per_user: Option<RateLimit>,
+    per_chat: Option<RateLimit>,
+    cooldown_notice: Option<String>,
+    state: Arc<Mutex<FloodState>>,
+}
+
+impl FloodControl {
+    pub fn new() -> FloodControl {
+        FloodControl::default()
+    }
+
+    pub fn per_user(mut self, max_updates: usize, window: Duration) -> FloodControl {
+        self.per_user = Some(RateLimit {
+            max_updates,
+            window,
+        });
+        self
+    }
+
+    pub fn per_chat(mut self, max_updates: usize, window: Duration) -> FloodControl {
+        self.per_chat = Some(RateLimit {
+            max_updates,
+            window,
+        });
+        self
+    }
+
+    /// Sent to the chat once when a user or chat gets throttled.
+    pub fn with_cooldown_notice(mut self, text: impl Into<String>) -> FloodControl {
+        self.cooldown_notice = Some(text.into());
+        self
+    }
+
+    pub fn cooldown_notice(&self) -> Option<&str> {
+        self.cooldown_notice.as_deref()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.per_user.is_some() || self.per_chat.is_some()
+    }
+
+    /// Records an update at `now` unless the user or the chat is over its
+    /// limit.
+    pub fn hit(
+        &self,
+        user_id: Option<u64>,
+        chat_id: Option<i64>,
+        now: Instant,
+    ) -> Result<(), Throttled> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+
+        sweep(state, self.longest_window(), now);
+
+        let mut user_entry = match (user_id, self.per_user) {
+            (Some(user_id), Some(limit)) => Some((state.users.entry(user_id).or_default(), limit)),
+            _ => None,
+        };
+        if let Some((entry, limit)) = &mut user_entry {
+            check(entry, *limit, ThrottleScope::User, now)?;
+        }
+
+        let mut chat_entry = match (chat_id, self.per_chat) {
+            (Some(chat_id), Some(limit)) => Some((state.chats.entry(chat_id).or_default(), limit)),
+            _ => None,
+        };
+        if let Some((entry, limit)) = &mut chat_entry {
+            check(entry, *limit, ThrottleScope::Chat, now)?;
+        }
+
+        for (entry, _) in user_entry.into_iter().chain(chat_entry) {
+            entry.hits.push_back(now);
+            entry.throttled = false;
+        }
+
+        Ok(())
+    }
+
+    fn longest_window(&self) -> Duration {
+        [self.per_user, self.per_chat]
+            .iter()
+            .flatten()
+            .map(|limit| limit.window)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+fn check(
+    entry: &mut FloodEntry,
+    limit: RateLimit,
+    scope: ThrottleScope,
+    now: Instant,
+) -> Result<(), Throttled> {
+    while let Some(hit) = entry.hits.front() {
+        if now.duration_since(*hit) < limit.window {
+            break;
+        }
+        entry.hits.pop_front();
+    }
+
+    if entry.hits.len() < limit.max_updates {
+        return Ok(());
+    }
+
+    let oldest = entry.hits.front().copied().unwrap_or(now);
+    let first = !entry.throttled;
+    entry.throttled = true;
+
+    Err(Throttled {
+        scope,
+        retry_after: limit.window.saturating_sub(now.duration_since(oldest)),
+        first,
+    })
+}
+
+/// Forgets users and chats without hits in the last `window`.
+fn sweep(state: &mut FloodState, window: Duration, now: Instant) {
+    let due = state
+        .last_sweep
+        .map(|last_sweep| now.duration_since(last_sweep) >= window)
+        .unwrap_or(true);
+
+    if !due {
+        return;
+    }
+
+    let is_active = |entry: &FloodEntry| {
+        entry
+            .hits
+            .back()
+            .map(|hit| now.duration_since(*hit) < window)
+            .unwrap_or(false)
+    };
+
+    state.users.retain(|_, entry| is_active(entry));
+    state.chats.retain(|_, entry| is_active(entry));
+    state.last_sweep = Some(now);
+}
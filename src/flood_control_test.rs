@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+
+use super::{FloodControl, ThrottleScope, Throttled};
+
+#[test]
+fn nothing_is_throttled_by_default() {
+    let flood_control = FloodControl::new();
+    let now = Instant::now();
+
+    assert!(!flood_control.is_enabled());
+    for _ in 0..100 {
+        assert_eq!(flood_control.hit(Some(1), Some(1), now), Ok(()));
+    }
+}
+
+#[test]
+fn user_is_throttled_within_window() {
+    let flood_control = FloodControl::new().per_user(2, Duration::from_secs(10));
+    let now = Instant::now();
+
+    assert_eq!(flood_control.hit(Some(1), Some(1), now), Ok(()));
+    assert_eq!(
+        flood_control.hit(Some(1), Some(1), now + Duration::from_secs(4)),
+        Ok(())
+    );
+    assert_eq!(
+        flood_control.hit(Some(1), Some(1), now + Duration::from_secs(6)),
+        Err(Throttled {
+            scope: ThrottleScope::User,
+            retry_after: Duration::from_secs(4),
+            first: true,
+        })
+    );
+    assert_eq!(
+        flood_control
+            .hit(Some(1), Some(1), now + Duration::from_secs(7))
+            .map_err(|throttled| throttled.first),
+        Err(false)
+    );
+    assert_eq!(
+        flood_control.hit(Some(2), Some(1), now + Duration::from_secs(7)),
+        Ok(())
+    );
+    assert_eq!(
+        flood_control.hit(Some(1), Some(1), now + Duration::from_secs(10)),
+        Ok(())
+    );
+}
+
+#[test]
+fn chat_is_throttled_across_users() {
+    let flood_control = FloodControl::new()
+        .per_user(5, Duration::from_secs(10))
+        .per_chat(2, Duration::from_secs(10));
+    let now = Instant::now();
+
+    assert_eq!(flood_control.hit(Some(1), Some(-100), now), Ok(()));
+    assert_eq!(flood_control.hit(Some(2), Some(-100), now), Ok(()));
+    assert_eq!(
+        flood_control
+            .hit(Some(3), Some(-100), now)
+            .map_err(|throttled| throttled.scope),
+        Err(ThrottleScope::Chat)
+    );
+    assert_eq!(flood_control.hit(Some(3), Some(-200), now), Ok(()));
+}
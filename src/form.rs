@@ -0,0 +1,435 @@
+#[cfg(test)]
+#[path = "form_test.rs"]
+mod form_test;
+
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ReplyMarkup};
+
+use crate::bloc_event::BlocEvent;
+use crate::bloc_state::BlocState;
+use crate::dialogue::{Dialogue, DialogueContext, DialogueInput, DialogueReply};
+
+const CALLBACK_PREFIX: &str = "form:";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldKind {
+    Text,
+    Number,
+    Choice {
+        options: Vec<String>,
+    },
+    /// `YYYY-MM-DD`.
+    Date,
+    /// Stored as the `file_id` of the largest size.
+    Photo,
+    /// Stored as `{"latitude": .., "longitude": ..}`.
+    Location,
+}
+
+type Validator = Arc<dyn Fn(&Value) -> Result<(), String> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct FormField {
+    pub name: String,
+    pub question: String,
+    pub kind: FieldKind,
+    validator: Option<Validator>,
+}
+
+/// Where a chat is in the form, kept in the dialogue storage.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FormProgress {
+    pub field: usize,
+    pub values: Map<String, Value>,
+    pub confirming: bool,
+}
+
+/// Answers of a completed form by field name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormValues(Map<String, Value>);
+
+impl FormValues {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    /// Converts the answers into a struct with fields named after the form fields.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_value(Value::Object(self.0.clone()))
+    }
+}
+
+/// Asks the fields one by one, re-asks on invalid answers and optionally
+/// confirms the answers at the end. Runs as a dialogue started by `/<name>`
+/// and emits `FormCompleted` or `FormCancelled`.
+#[derive(Clone)]
+pub struct Form {
+    name: String,
+    fields: Vec<FormField>,
+    confirmation: Option<String>,
+}
+
+impl Form {
+    pub fn new(name: impl Into<String>) -> Form {
+        Form {
+            name: name.into().trim_start_matches('/').to_lowercase(),
+            fields: Vec::new(),
+            confirmation: None,
+        }
+    }
+
+    pub fn text(self, name: impl Into<String>, question: impl Into<String>) -> Form {
+        self.field(name, question, FieldKind::Text)
+    }
+
+    pub fn number(self, name: impl Into<String>, question: impl Into<String>) -> Form {
+        self.field(name, question, FieldKind::Number)
+    }
+
+    pub fn choice<O: Into<String>>(
+        self,
+        name: impl Into<String>,
+        question: impl Into<String>,
+        options: impl IntoIterator<Item = O>,
+    ) -> Form {
+        let options = options.into_iter().map(Into::into).collect();
+        self.field(name, question, FieldKind::Choice { options })
+    }
+
+    pub fn date(self, name: impl Into<String>, question: impl Into<String>) -> Form {
+        self.field(name, question, FieldKind::Date)
+    }
+
+    pub fn photo(self, name: impl Into<String>, question: impl Into<String>) -> Form {
+        self.field(name, question, FieldKind::Photo)
+    }
+
+    pub fn location(self, name: impl Into<String>, question: impl Into<String>) -> Form {
+        self.field(name, question, FieldKind::Location)
+    }
+
+    pub fn field(
+        mut self,
+        name: impl Into<String>,
+        question: impl Into<String>,
+        kind: FieldKind,
+    ) -> Form {
+        self.fields.push(FormField {
+            name: name.into(),
+            question: question.into(),
+            kind,
+            validator: None,
+        });
+        self
+    }
+
+    /// Validates the answer of the last added field, the error is sent back
+    /// before asking again.
+    pub fn validate<F>(mut self, validator: F) -> Form
+    where
+        F: Fn(&Value) -> Result<(), String> + Send + Sync + 'static,
+    {
+        if let Some(field) = self.fields.last_mut() {
+            field.validator = Some(Arc::new(validator));
+        }
+        self
+    }
+
+    pub fn confirm(mut self, question: impl Into<String>) -> Form {
+        self.confirmation = Some(question.into());
+        self
+    }
+
+    pub fn fields(&self) -> &[FormField] {
+        &self.fields
+    }
+
+    pub fn into_dialogue(self) -> Dialogue<FormProgress> {
+        let form = Arc::new(self);
+        let form_for_start = form.clone();
+        let form_for_step = form.clone();
+        let form_for_cancel = form.clone();
+
+        Dialogue::new(form.name.clone(), move |context| {
+            let form = form_for_start.clone();
+            async move {
+                let progress = FormProgress {
+                    field: 0,
+                    values: Map::new(),
+                    confirming: false,
+                };
+
+                form.next(context.chat_id, progress, Vec::new())
+            }
+        })
+        .step(
+            |_| true,
+            move |context| {
+                let form = form_for_step.clone();
+                async move { form.answer(context) }
+            },
+        )
+        .on_cancel(move |context| {
+            let form = form_for_cancel.clone();
+            async move { form.cancel(context.chat_id, Vec::new()) }
+        })
+    }
+
+    fn answer(&self, context: DialogueContext<FormProgress>) -> DialogueReply<FormProgress> {
+        let chat_id = context.chat_id;
+        let mut progress = match context.state {
+            Some(progress) => progress,
+            None => return DialogueReply::exit(Vec::new()),
+        };
+        let mut events = answer_callback_query(&context.input);
+
+        if progress.confirming {
+            return match parse_confirmation(&context.input) {
+                Some(true) => self.complete(chat_id, progress, events),
+                Some(false) => self.cancel(chat_id, events),
+                None => {
+                    events.push(self.confirmation_event(chat_id, &progress));
+                    DialogueReply::next(progress, events)
+                }
+            };
+        }
+
+        let field = match self.fields.get(progress.field) {
+            Some(field) => field,
+            None => return self.next(chat_id, progress, events),
+        };
+
+        let value =
+            parse_answer(&field.kind, &context.input).and_then(|value| match &field.validator {
+                Some(validator) => validator(&value).map(|_| value),
+                None => Ok(value),
+            });
+
+        match value {
+            Ok(value) => {
+                progress.values.insert(field.name.clone(), value);
+                progress.field += 1;
+
+                self.next(chat_id, progress, events)
+            }
+            Err(error) => {
+                events.push(BlocEvent::TextToChatSend {
+                    chat_id,
+                    text: error,
+                });
+                events.push(question_event(chat_id, field));
+
+                DialogueReply::next(progress, events)
+            }
+        }
+    }
+
+    /// Asks the current field, the confirmation or completes the form.
+    fn next(
+        &self,
+        chat_id: i64,
+        mut progress: FormProgress,
+        mut events: Vec<BlocEvent>,
+    ) -> DialogueReply<FormProgress> {
+        if let Some(field) = self.fields.get(progress.field) {
+            events.push(question_event(chat_id, field));
+            return DialogueReply::next(progress, events);
+        }
+
+        if self.confirmation.is_some() {
+            progress.confirming = true;
+            events.push(self.confirmation_event(chat_id, &progress));
+
+            return DialogueReply::next(progress, events);
+        }
+
+        self.complete(chat_id, progress, events)
+    }
+
+    fn complete(
+        &self,
+        chat_id: i64,
+        progress: FormProgress,
+        events: Vec<BlocEvent>,
+    ) -> DialogueReply<FormProgress> {
+        let state = BlocState::FormCompleted {
+            chat_id,
+            form: self.name.clone(),
+            values: FormValues(progress.values),
+        };
+
+        DialogueReply::exit(events).with_state(state)
+    }
+
+    fn cancel(&self, chat_id: i64, events: Vec<BlocEvent>) -> DialogueReply<FormProgress> {
+        let state = BlocState::FormCancelled {
+            chat_id,
+            form: self.name.clone(),
+        };
+
+        DialogueReply::exit(events).with_state(state)
+    }
+
+    fn confirmation_event(&self, chat_id: i64, progress: &FormProgress) -> BlocEvent {
+        let summary = self
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let value = progress.values.get(&field.name)?;
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+
+                Some(format!("{}: {}", field.name, value))
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let question = self.confirmation.clone().unwrap_or_default();
+
+        BlocEvent::TextToChatSendWithMarkup {
+            chat_id,
+            text: format!("{}\n\n{}", summary, question),
+            markup: ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::callback("Yes", format!("{}yes", CALLBACK_PREFIX)),
+                InlineKeyboardButton::callback("No", format!("{}no", CALLBACK_PREFIX)),
+            ]])),
+        }
+    }
+}
+
+fn question_event(chat_id: i64, field: &FormField) -> BlocEvent {
+    match &field.kind {
+        FieldKind::Choice { options } => {
+            let buttons = options
+                .iter()
+                .enumerate()
+                .map(|(index, option)| {
+                    vec![InlineKeyboardButton::callback(
+                        option.clone(),
+                        format!("{}{}", CALLBACK_PREFIX, index),
+                    )]
+                })
+                .collect::<Vec<Vec<InlineKeyboardButton>>>();
+
+            BlocEvent::TextToChatSendWithMarkup {
+                chat_id,
+                text: field.question.clone(),
+                markup: ReplyMarkup::InlineKeyboard(InlineKeyboardMarkup::new(buttons)),
+            }
+        }
+        _ => BlocEvent::TextToChatSend {
+            chat_id,
+            text: field.question.clone(),
+        },
+    }
+}
+
+fn answer_callback_query(input: &DialogueInput) -> Vec<BlocEvent> {
+    match input {
+        DialogueInput::CallbackQuery(callback_query) => vec![BlocEvent::AnswerCallbackQuery {
+            callback_query_id: callback_query.id.clone(),
+            text: None,
+            show_alert: false,
+            url: None,
+            cache_time: None,
+        }],
+        _ => Vec::new(),
+    }
+}
+
+fn callback_data(input: &DialogueInput) -> Option<&str> {
+    match input {
+        DialogueInput::CallbackQuery(callback_query) => callback_query
+            .data
+            .as_deref()
+            .and_then(|data| data.strip_prefix(CALLBACK_PREFIX)),
+        _ => None,
+    }
+}
+
+fn input_text(input: &DialogueInput) -> Option<&str> {
+    match input {
+        DialogueInput::Message(message) => message.text(),
+        _ => None,
+    }
+}
+
+fn parse_confirmation(input: &DialogueInput) -> Option<bool> {
+    let answer = callback_data(input).or_else(|| input_text(input))?;
+
+    match answer.trim().to_lowercase().as_str() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+pub(crate) fn parse_answer(kind: &FieldKind, input: &DialogueInput) -> Result<Value, String> {
+    let text = input_text(input).map(str::trim);
+
+    match kind {
+        FieldKind::Text => match text {
+            Some(text) if !text.is_empty() => Ok(Value::String(text.to_string())),
+            _ => Err("Please send text.".to_string()),
+        },
+        FieldKind::Number => {
+            let text = text.ok_or_else(|| "Please send a number.".to_string())?;
+
+            if let Ok(number) = text.parse::<i64>() {
+                return Ok(json!(number));
+            }
+
+            text.parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite())
+                .map(|number| json!(number))
+                .ok_or_else(|| "Please send a number.".to_string())
+        }
+        FieldKind::Choice { options } => {
+            let selected = match callback_data(input) {
+                Some(index) => index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| options.get(index)),
+                None => text.and_then(|text| {
+                    options
+                        .iter()
+                        .find(|option| option.eq_ignore_ascii_case(text))
+                }),
+            };
+
+            selected
+                .map(|option| Value::String(option.clone()))
+                .ok_or_else(|| "Please pick one of the options.".to_string())
+        }
+        FieldKind::Date => text
+            .and_then(|text| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok())
+            .map(|date| Value::String(date.to_string()))
+            .ok_or_else(|| "Please send a date as YYYY-MM-DD.".to_string()),
+        FieldKind::Photo => match input {
+            DialogueInput::Message(message) => message
+                .photo()
+                .and_then(|sizes| sizes.iter().max_by_key(|size| size.width * size.height))
+                .map(|size| Value::String(size.file_id.clone()))
+                .ok_or_else(|| "Please send a photo.".to_string()),
+            _ => Err("Please send a photo.".to_string()),
+        },
+        FieldKind::Location => match input {
+            DialogueInput::Message(message) => message
+                .location()
+                .map(|location| {
+                    json!({"latitude": location.latitude, "longitude": location.longitude})
+                })
+                .ok_or_else(|| "Please share a location.".to_string()),
+            _ => Err("Please share a location.".to_string()),
+        },
+    }
+}
@@ -0,0 +1,122 @@
+use serde_derive::Deserialize;
+use serde_json::json;
+
+use crate::bloc_state::BlocState;
+use crate::dialogue::{DialogueInput, DialogueRunner};
+use crate::test_fixtures::{callback_query_from_data, dialogue_input};
+
+use super::{parse_answer, FieldKind, Form};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Registration {
+    name: String,
+    age: u32,
+    plan: String,
+}
+
+fn registration() -> Form {
+    Form::new("register")
+        .text("name", "What is your name?")
+        .number("age", "How old are you?")
+        .validate(|value| match value.as_i64() {
+            Some(age) if age >= 18 => Ok(()),
+            _ => Err("You must be 18 or older.".to_string()),
+        })
+        .choice("plan", "Pick a plan", ["free", "pro"])
+        .confirm("Is everything correct?")
+}
+
+#[tokio::test]
+async fn completed_form_yields_values() {
+    let (state_controller, state_stream) = async_channel::unbounded();
+    let dialogue = registration().into_dialogue();
+
+    for text in ["/register", "Alice", "16", "30"] {
+        dialogue
+            .handle(10, dialogue_input(text), &state_controller)
+            .await
+            .unwrap();
+    }
+
+    let plan = DialogueInput::CallbackQuery(callback_query_from_data("form:1"));
+    dialogue.handle(10, plan, &state_controller).await.unwrap();
+
+    assert!(state_stream.try_recv().is_err());
+
+    let confirmation = DialogueInput::CallbackQuery(callback_query_from_data("form:yes"));
+    dialogue
+        .handle(10, confirmation, &state_controller)
+        .await
+        .unwrap();
+
+    match state_stream.try_recv().unwrap() {
+        BlocState::FormCompleted { form, values, .. } => {
+            assert_eq!(form, "register");
+            assert_eq!(
+                values.deserialize::<Registration>().unwrap(),
+                Registration {
+                    name: "Alice".to_string(),
+                    age: 30,
+                    plan: "pro".to_string(),
+                }
+            );
+        }
+        state => panic!("Unexpected state {}", state),
+    }
+}
+
+#[tokio::test]
+async fn cancelled_form_emits_cancellation() {
+    let (state_controller, state_stream) = async_channel::unbounded();
+    let dialogue = registration().into_dialogue();
+
+    for text in ["/register", "Alice", "/cancel"] {
+        dialogue
+            .handle(10, dialogue_input(text), &state_controller)
+            .await
+            .unwrap();
+    }
+
+    let states = std::iter::from_fn(|| state_stream.try_recv().ok())
+        .map(|state| state.to_string())
+        .collect::<Vec<String>>();
+
+    assert_eq!(
+        states,
+        vec![
+            "DialogueCancelled{chat_id:10}",
+            "FormCancelled{chat_id:10, form:register}"
+        ]
+    );
+    assert_eq!(dialogue.state(10).await, None);
+}
+
+#[test]
+fn answers_are_parsed_by_kind() {
+    assert_eq!(
+        parse_answer(&FieldKind::Number, &dialogue_input("42")),
+        Ok(json!(42))
+    );
+    assert_eq!(
+        parse_answer(&FieldKind::Number, &dialogue_input("4.5")),
+        Ok(json!(4.5))
+    );
+    assert!(parse_answer(&FieldKind::Number, &dialogue_input("many")).is_err());
+
+    assert_eq!(
+        parse_answer(&FieldKind::Date, &dialogue_input("2022-07-01")),
+        Ok(json!("2022-07-01"))
+    );
+    assert!(parse_answer(&FieldKind::Date, &dialogue_input("2022-13-01")).is_err());
+
+    let choice = FieldKind::Choice {
+        options: vec!["free".to_string(), "pro".to_string()],
+    };
+    assert_eq!(
+        parse_answer(&choice, &dialogue_input("PRO")),
+        Ok(json!("pro"))
+    );
+    assert!(parse_answer(&choice, &dialogue_input("enterprise")).is_err());
+
+    assert!(parse_answer(&FieldKind::Photo, &dialogue_input("photo")).is_err());
+}
@@ -0,0 +1,93 @@
+#[cfg(test)]
+#[path = "inline_results_test.rs"]
+mod inline_results_test;
+
+use url::Url;
+
+use teloxide::types::{
+    InlineQueryResult, InlineQueryResultArticle, InlineQueryResultCachedDocument,
+    InlineQueryResultCachedGif, InlineQueryResultCachedPhoto, InlineQueryResultDocument,
+    InlineQueryResultGif, InlineQueryResultPhoto, InputMessageContent, InputMessageContentText,
+};
+
+/// Telegram accepts at most 50 results per answer.
+pub const MAX_INLINE_RESULTS: usize = 50;
+
+pub fn article(
+    id: impl Into<String>,
+    title: impl Into<String>,
+    text: impl Into<String>,
+) -> InlineQueryResult {
+    let content = InputMessageContent::Text(InputMessageContentText::new(text));
+    InlineQueryResult::Article(InlineQueryResultArticle::new(id, title, content))
+}
+
+pub fn photo(id: impl Into<String>, photo_url: Url, thumb_url: Url) -> InlineQueryResult {
+    InlineQueryResult::Photo(InlineQueryResultPhoto::new(id, photo_url, thumb_url))
+}
+
+pub fn gif(id: impl Into<String>, gif_url: Url, thumb_url: Url) -> InlineQueryResult {
+    InlineQueryResult::Gif(InlineQueryResultGif::new(id, gif_url, thumb_url))
+}
+
+/// Only PDF and ZIP documents can be sent by URL, the type is taken from the
+/// URL extension.
+pub fn document(
+    id: impl Into<String>,
+    title: impl Into<String>,
+    document_url: Url,
+) -> InlineQueryResult {
+    let mime_type = if document_url.path().to_lowercase().ends_with(".zip") {
+        "application/zip".parse().unwrap()
+    } else {
+        mime::APPLICATION_PDF
+    };
+
+    InlineQueryResult::Document(InlineQueryResultDocument {
+        id: id.into(),
+        title: title.into(),
+        caption: None,
+        parse_mode: None,
+        caption_entities: None,
+        document_url,
+        mime_type,
+        description: None,
+        reply_markup: None,
+        input_message_content: None,
+        thumb_url: None,
+        thumb_width: None,
+        thumb_height: None,
+    })
+}
+
+pub fn cached_photo(id: impl Into<String>, file_id: impl Into<String>) -> InlineQueryResult {
+    InlineQueryResult::CachedPhoto(InlineQueryResultCachedPhoto::new(id, file_id))
+}
+
+pub fn cached_gif(id: impl Into<String>, file_id: impl Into<String>) -> InlineQueryResult {
+    InlineQueryResult::CachedGif(InlineQueryResultCachedGif::new(id, file_id))
+}
+
+pub fn cached_document(
+    id: impl Into<String>,
+    title: impl Into<String>,
+    file_id: impl Into<String>,
+) -> InlineQueryResult {
+    InlineQueryResult::CachedDocument(InlineQueryResultCachedDocument::new(id, title, file_id))
+}
+
+/// Takes the page starting at `offset` from `InlineQuery::offset` and returns
+/// it with the `next_offset` to answer with, empty when there are no more results.
+pub fn paginate<T: Clone>(results: &[T], offset: &str, page_size: usize) -> (Vec<T>, String) {
+    let page_size = page_size.clamp(1, MAX_INLINE_RESULTS);
+    let start = offset.parse::<usize>().unwrap_or(0).min(results.len());
+    let end = (start + page_size).min(results.len());
+
+    let next_offset = if end < results.len() {
+        end.to_string()
+    } else {
+        String::new()
+    };
+
+    (results[start..end].to_vec(), next_offset)
+}
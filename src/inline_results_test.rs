@@ -0,0 +1,55 @@
+use teloxide::types::InlineQueryResult;
+use url::Url;
+
+use super::{document, paginate, MAX_INLINE_RESULTS};
+
+#[test]
+fn first_page_starts_from_empty_offset() {
+    let results: Vec<u32> = (0..25).collect();
+
+    let (page, next_offset) = paginate(&results, "", 10);
+
+    assert_eq!(page, (0..10).collect::<Vec<u32>>());
+    assert_eq!(next_offset, "10");
+}
+
+#[test]
+fn last_page_has_empty_next_offset() {
+    let results: Vec<u32> = (0..25).collect();
+
+    let (page, next_offset) = paginate(&results, "20", 10);
+
+    assert_eq!(page, (20..25).collect::<Vec<u32>>());
+    assert_eq!(next_offset, "");
+}
+
+#[test]
+fn offset_past_the_end_gives_empty_page() {
+    let results: Vec<u32> = (0..5).collect();
+
+    let (page, next_offset) = paginate(&results, "40", 10);
+
+    assert!(page.is_empty());
+    assert_eq!(next_offset, "");
+}
+
+#[test]
+fn page_size_is_limited_by_telegram() {
+    let results: Vec<u32> = (0..100).collect();
+
+    let (page, _) = paginate(&results, "", 500);
+
+    assert_eq!(page.len(), MAX_INLINE_RESULTS);
+}
+
+#[test]
+fn document_mime_type_is_taken_from_url() {
+    let url = Url::parse("https://example.com/archive.ZIP").unwrap();
+
+    match document("1", "Archive", url) {
+        InlineQueryResult::Document(document) => {
+            assert_eq!(document.mime_type.essence_str(), "application/zip")
+        }
+        _ => panic!("Expected document result"),
+    }
+}
@@ -5,6 +5,28 @@ pub mod bloc_with_autosend_bot_with_default_parse_mode;
 pub mod bloc_event;
 pub mod bloc_state;
 
+pub mod access_control;
+pub mod captcha;
+pub mod chat_administration;
+pub mod command_registry;
+pub mod deep_link;
+pub mod dialogue;
+pub mod dialogue_storage;
+pub mod download;
+pub mod file_cache;
+pub mod flood_control;
+pub mod form;
+pub mod inline_results;
+pub mod media_source;
+pub mod middleware;
+pub mod parsed_command;
+pub mod router;
+pub mod uploaded_files;
+
 pub mod bloc_mock;
 
+#[cfg(test)]
+mod test_fixtures;
+
+mod update_handler;
 mod webhook;
@@ -0,0 +1,199 @@
+#[cfg(test)]
+#[path = "media_source_test.rs"]
+mod media_source_test;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use tokio::io::AsyncRead;
+
+use teloxide::types::{
+    InputFile, InputMedia, InputMediaAnimation, InputMediaAudio, InputMediaDocument,
+    InputMediaPhoto, InputMediaVideo,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaKind {
+    Photo,
+    Video,
+    Animation,
+    Audio,
+    Document,
+}
+
+/// In-memory or streamed file for upload, without a temporary file on disk.
+#[derive(Clone)]
+pub struct MediaSource {
+    file: InputFile,
+    file_name: String,
+    mime_type: Option<String>,
+    path: Option<PathBuf>,
+}
+
+impl MediaSource {
+    pub fn bytes(bytes: Vec<u8>, file_name: impl Into<String>) -> MediaSource {
+        let file_name = file_name.into();
+
+        MediaSource {
+            file: InputFile::memory(bytes).file_name(file_name.clone()),
+            file_name,
+            mime_type: None,
+            path: None,
+        }
+    }
+
+    pub fn reader(
+        reader: impl AsyncRead + Send + Unpin + 'static,
+        file_name: impl Into<String>,
+    ) -> MediaSource {
+        let file_name = file_name.into();
+
+        MediaSource {
+            file: InputFile::read(reader).file_name(file_name.clone()),
+            file_name,
+            mime_type: None,
+            path: None,
+        }
+    }
+
+    pub fn path(path: impl Into<PathBuf>) -> MediaSource {
+        let path = path.into();
+        let file_name = path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        MediaSource {
+            file: InputFile::file(path.clone()),
+            file_name,
+            mime_type: None,
+            path: Some(path),
+        }
+    }
+
+    pub fn mime_type(mut self, mime_type: impl Into<String>) -> MediaSource {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    pub fn get_file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn get_mime_type(&self) -> Option<&str> {
+        self.mime_type.as_deref()
+    }
+
+    pub fn get_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Sends an already uploaded file instead of uploading the content again.
+    pub(crate) fn with_file_id(mut self, file_id: String) -> MediaSource {
+        self.file = InputFile::file_id(file_id);
+        self
+    }
+
+    /// Kind is taken from the MIME hint and falls back to the file extension.
+    pub fn kind(&self) -> MediaKind {
+        match &self.mime_type {
+            Some(mime_type) => kind_from_mime_type(mime_type),
+            None => kind_from_file_name(&self.file_name),
+        }
+    }
+
+    pub fn into_input_file(self) -> InputFile {
+        self.file
+    }
+
+    pub fn into_input_media(self, caption: Option<String>) -> InputMedia {
+        let kind = self.kind();
+        input_media(self.file, kind, caption)
+    }
+}
+
+impl fmt::Display for MediaSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format!(
+            "MediaSource{{file_name:{}, mime_type:{:?}}}",
+            self.file_name, self.mime_type
+        ))
+    }
+}
+
+/// Items of a `MediaSourcesToChatSend` album. `sendMediaGroup` doesn't accept
+/// animations, so GIFs are sent as documents.
+pub(crate) fn album_media(sources: impl IntoIterator<Item = MediaSource>) -> Vec<InputMedia> {
+    sources
+        .into_iter()
+        .map(|source| {
+            let kind = match source.kind() {
+                MediaKind::Animation => MediaKind::Document,
+                kind => kind,
+            };
+
+            input_media(source.file, kind, None)
+        })
+        .collect()
+}
+
+fn input_media(file: InputFile, kind: MediaKind, caption: Option<String>) -> InputMedia {
+    match kind {
+        MediaKind::Photo => {
+            let mut media = InputMediaPhoto::new(file);
+            media.caption = caption;
+            InputMedia::Photo(media)
+        }
+        MediaKind::Video => {
+            let mut media = InputMediaVideo::new(file);
+            media.caption = caption;
+            InputMedia::Video(media)
+        }
+        MediaKind::Animation => {
+            let mut media = InputMediaAnimation::new(file);
+            media.caption = caption;
+            InputMedia::Animation(media)
+        }
+        MediaKind::Audio => {
+            let mut media = InputMediaAudio::new(file);
+            media.caption = caption;
+            InputMedia::Audio(media)
+        }
+        MediaKind::Document => {
+            let mut media = InputMediaDocument::new(file);
+            media.caption = caption;
+            InputMedia::Document(media)
+        }
+    }
+}
+
+pub(crate) fn kind_from_mime_type(mime_type: &str) -> MediaKind {
+    let mime_type = mime_type.to_lowercase();
+
+    if mime_type == "image/gif" {
+        MediaKind::Animation
+    } else if mime_type == "image/jpeg" || mime_type == "image/png" || mime_type == "image/webp" {
+        MediaKind::Photo
+    } else if mime_type.starts_with("video/") {
+        MediaKind::Video
+    } else if mime_type.starts_with("audio/") {
+        MediaKind::Audio
+    } else {
+        MediaKind::Document
+    }
+}
+
+pub(crate) fn kind_from_file_name(file_name: &str) -> MediaKind {
+    let extension = match file_name.rsplit_once('.') {
+        Some((_, extension)) => extension.to_lowercase(),
+        None => return MediaKind::Document,
+    };
+
+    match extension.as_str() {
+        "jpg" | "jpeg" | "png" | "webp" => MediaKind::Photo,
+        "gif" => MediaKind::Animation,
+        "mp4" | "mov" | "webm" => MediaKind::Video,
+        "mp3" | "m4a" | "ogg" | "flac" | "wav" => MediaKind::Audio,
+        _ => MediaKind::Document,
+    }
+}
@@ -0,0 +1,77 @@
+use teloxide::types::InputMedia;
+
+use super::{album_media, kind_from_file_name, kind_from_mime_type, MediaKind, MediaSource};
+
+#[test]
+fn kind_is_taken_from_mime_type() {
+    assert_eq!(kind_from_mime_type("image/png"), MediaKind::Photo);
+    assert_eq!(kind_from_mime_type("IMAGE/GIF"), MediaKind::Animation);
+    assert_eq!(kind_from_mime_type("video/mp4"), MediaKind::Video);
+    assert_eq!(kind_from_mime_type("audio/mpeg"), MediaKind::Audio);
+    assert_eq!(kind_from_mime_type("application/pdf"), MediaKind::Document);
+    assert_eq!(kind_from_mime_type("image/svg+xml"), MediaKind::Document);
+}
+
+#[test]
+fn kind_falls_back_to_file_extension() {
+    assert_eq!(kind_from_file_name("chart.PNG"), MediaKind::Photo);
+    assert_eq!(kind_from_file_name("report.pdf"), MediaKind::Document);
+    assert_eq!(kind_from_file_name("README"), MediaKind::Document);
+}
+
+#[test]
+fn mime_type_overrides_file_extension() {
+    let source = MediaSource::bytes(vec![1, 2, 3], "chart.png").mime_type("application/pdf");
+    assert_eq!(source.kind(), MediaKind::Document);
+
+    let media = source.into_input_media(Some("Report".to_string()));
+    match media {
+        InputMedia::Document(document) => {
+            assert_eq!(document.caption, Some("Report".to_string()))
+        }
+        _ => panic!("Expected document media"),
+    }
+}
+
+#[test]
+fn gif_is_sent_as_document_in_album() {
+    let media = album_media(vec![
+        MediaSource::bytes(vec![1, 2, 3], "chart.png"),
+        MediaSource::bytes(vec![1, 2, 3], "loop.gif"),
+        MediaSource::bytes(vec![1, 2, 3], "loop").mime_type("image/gif"),
+    ]);
+
+    assert!(matches!(
+        media.as_slice(),
+        [
+            InputMedia::Photo(_),
+            InputMedia::Document(_),
+            InputMedia::Document(_)
+        ]
+    ));
+    assert!(matches!(
+        MediaSource::bytes(vec![1, 2, 3], "loop.gif").into_input_media(None),
+        InputMedia::Animation(_)
+    ));
+}
+
+#[tokio::test]
+async fn reader_keeps_file_name() {
+    let reader = tokio::io::empty();
+    let source = MediaSource::reader(reader, "data.csv");
+
+    assert_eq!(source.get_file_name(), "data.csv");
+    assert_eq!(source.kind(), MediaKind::Document);
+}
+
+#[test]
+fn path_source_takes_file_name_from_path() {
+    let source = MediaSource::path("/tmp/charts/daily.png");
+
+    assert_eq!(source.get_file_name(), "daily.png");
+    assert_eq!(
+        source.get_path(),
+        Some(std::path::Path::new("/tmp/charts/daily.png"))
+    );
+    assert_eq!(source.kind(), MediaKind::Photo);
+}
@@ -0,0 +1,196 @@
+#[cfg(test)]
+#[path = "middleware_test.rs"]
+mod middleware_test;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_channel::Sender;
+use async_trait::async_trait;
+use tokio::task::JoinHandle;
+
+use teloxide::dptree;
+use teloxide::types::Update;
+
+use crate::bloc::BotUpdateHandler;
+use crate::bloc_event::BlocEvent;
+use crate::bloc_state::BlocState;
+use crate::update_handler::entry_description;
+
+/// Values attached to an update by middlewares. Handlers can take it as a
+/// dependency.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UpdateExtensions(HashMap<String, String>);
+
+impl UpdateExtensions {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.0.insert(key.into(), value.into());
+    }
+}
+
+#[async_trait]
+pub trait UpdateMiddleware: Send + Sync {
+    /// Returns the update to pass on, possibly changed, or `None` to drop it.
+    async fn handle(&self, update: Update, extensions: &mut UpdateExtensions) -> Option<Update>;
+}
+
+/// Logs every update before it is handled.
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl UpdateMiddleware for LoggingMiddleware {
+    async fn handle(&self, update: Update, _extensions: &mut UpdateExtensions) -> Option<Update> {
+        let log_message = format!(
+            "Update {} from chat {:?}.",
+            update.id,
+            update.chat().map(|chat| chat.id.0)
+        );
+        log::info!("{}", log_message);
+
+        Some(update)
+    }
+}
+
+/// Stores the sender's language under `language_code`.
+pub struct LanguageMiddleware;
+
+#[async_trait]
+impl UpdateMiddleware for LanguageMiddleware {
+    async fn handle(&self, update: Update, extensions: &mut UpdateExtensions) -> Option<Update> {
+        let language_code = update.user().and_then(|user| user.language_code.clone());
+
+        if let Some(language_code) = language_code {
+            extensions.insert("language_code", language_code);
+        }
+
+        Some(update)
+    }
+}
+
+/// Middlewares registered on the Bloc, run in order before the update handler.
+#[derive(Clone, Default)]
+pub(crate) struct UpdateMiddlewares(Vec<Arc<dyn UpdateMiddleware>>);
+
+impl UpdateMiddlewares {
+    pub(crate) fn push(&mut self, middleware: Arc<dyn UpdateMiddleware>) {
+        self.0.push(middleware);
+    }
+
+    pub(crate) async fn run(&self, mut update: Update) -> Option<(Update, UpdateExtensions)> {
+        let mut extensions = UpdateExtensions::default();
+
+        for middleware in &self.0 {
+            update = middleware.handle(update, &mut extensions).await?;
+        }
+
+        Some((update, extensions))
+    }
+
+    /// Puts the middlewares in front of `handler`. The update they return
+    /// replaces the original one and the extensions become a dependency.
+    pub(crate) fn wrap(&self, handler: BotUpdateHandler) -> BotUpdateHandler {
+        let middlewares = self.clone();
+
+        dptree::filter_map_async_with_description(entry_description(), move |update: Update| {
+            let middlewares = middlewares.clone();
+            async move { middlewares.run(update).await }
+        })
+        .chain(dptree::map_with_description(
+            entry_description(),
+            |(update, _): (Update, UpdateExtensions)| update,
+        ))
+        .chain(dptree::map_with_description(
+            entry_description(),
+            |(_, extensions): (Update, UpdateExtensions)| extensions,
+        ))
+        .chain(handler)
+    }
+}
+
+#[async_trait]
+pub trait EventMiddleware: Send + Sync {
+    /// Returns the event to send, possibly changed, or `None` to drop it.
+    /// Awaiting here delays the event and every event after it.
+    async fn before(&self, event: BlocEvent) -> Option<BlocEvent>;
+
+    /// Observes the states the event produced once it has been handled.
+    async fn after(&self, _event: &BlocEvent, _states: &[BlocState]) {}
+}
+
+/// Logs every event and the states it produced.
+pub struct EventLoggingMiddleware;
+
+#[async_trait]
+impl EventMiddleware for EventLoggingMiddleware {
+    async fn before(&self, event: BlocEvent) -> Option<BlocEvent> {
+        let log_message = format!("Handling event {}.", event);
+        log::info!("{}", log_message);
+
+        Some(event)
+    }
+
+    async fn after(&self, event: &BlocEvent, states: &[BlocState]) {
+        let states = states
+            .iter()
+            .map(|state| state.to_string())
+            .collect::<Vec<String>>();
+
+        let log_message = format!("Event {} resulted in {:?}.", event, states);
+        log::info!("{}", log_message);
+    }
+}
+
+/// Middlewares registered on the Bloc, run in order around every event.
+#[derive(Clone, Default)]
+pub(crate) struct EventMiddlewares(Vec<Arc<dyn EventMiddleware>>);
+
+impl EventMiddlewares {
+    pub(crate) fn push(&mut self, middleware: Arc<dyn EventMiddleware>) {
+        self.0.push(middleware);
+    }
+
+    pub(crate) async fn before(&self, mut event: BlocEvent) -> Option<BlocEvent> {
+        for middleware in &self.0 {
+            event = middleware.before(event).await?;
+        }
+
+        Some(event)
+    }
+
+    /// Returns the sender to emit the states of `event` with. The states are
+    /// forwarded to `state_controller` as they come and passed to `after` once
+    /// the returned sender is dropped, which the returned task waits for.
+    pub(crate) fn observe(
+        &self,
+        event: &BlocEvent,
+        state_controller: &Sender<BlocState>,
+    ) -> (Sender<BlocState>, Option<JoinHandle<()>>) {
+        if self.0.is_empty() {
+            return (state_controller.clone(), None);
+        }
+
+        let (observed_controller, observed_stream) = async_channel::unbounded::<BlocState>();
+        let state_controller = state_controller.clone();
+        let middlewares = self.clone();
+        let event = event.clone();
+
+        let observer = tokio::spawn(async move {
+            let mut states = Vec::new();
+
+            while let Ok(state) = observed_stream.recv().await {
+                let _ = state_controller.send(state.clone()).await;
+                states.push(state);
+            }
+
+            for middleware in &middlewares.0 {
+                middleware.after(&event, &states).await;
+            }
+        });
+
+        (observed_controller, Some(observer))
+    }
+}
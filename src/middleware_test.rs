@@ -0,0 +1,197 @@
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use teloxide::dptree;
+use teloxide::respond;
+use teloxide::types::{Update, UpdateKind};
+
+use crate::bloc_event::BlocEvent;
+use crate::bloc_state::BlocState;
+use crate::test_fixtures::{message_json, update, user_json};
+
+use super::{
+    EventMiddleware, EventMiddlewares, LanguageMiddleware, UpdateExtensions, UpdateMiddleware,
+    UpdateMiddlewares,
+};
+
+fn update_from_text(text: &str) -> Update {
+    let mut message = message_json(10, 10, "private", json!({ "text": text }));
+    message["from"] = user_json(10, json!({"language_code": "de"}));
+
+    update("message", message)
+}
+
+fn text(update: &Update) -> Option<String> {
+    match &update.kind {
+        UpdateKind::Message(message) => message.text().map(String::from),
+        _ => None,
+    }
+}
+
+struct BlockSpam;
+
+#[async_trait]
+impl UpdateMiddleware for BlockSpam {
+    async fn handle(&self, update: Update, _extensions: &mut UpdateExtensions) -> Option<Update> {
+        match text(&update) {
+            Some(text) if text.contains("spam") => None,
+            _ => Some(update),
+        }
+    }
+}
+
+struct Shout;
+
+#[async_trait]
+impl UpdateMiddleware for Shout {
+    async fn handle(
+        &self,
+        mut update: Update,
+        extensions: &mut UpdateExtensions,
+    ) -> Option<Update> {
+        if let UpdateKind::Message(message) = &mut update.kind {
+            let text = message.text().unwrap_or_default().to_uppercase();
+            *message = serde_json::from_value(serde_json::json!({
+                "message_id": message.id,
+                "date": 1600000000,
+                "chat": {"id": message.chat.id.0, "type": "private", "first_name": "User"},
+                "text": text
+            }))
+            .unwrap();
+        }
+
+        extensions.insert("shouted", "true");
+        Some(update)
+    }
+}
+
+fn middlewares() -> UpdateMiddlewares {
+    let mut middlewares = UpdateMiddlewares::default();
+    middlewares.push(Arc::new(LanguageMiddleware));
+    middlewares.push(Arc::new(BlockSpam));
+    middlewares.push(Arc::new(Shout));
+    middlewares
+}
+
+#[tokio::test]
+async fn middlewares_enrich_and_transform_in_order() {
+    let (update, extensions) = middlewares().run(update_from_text("hello")).await.unwrap();
+
+    assert_eq!(text(&update), Some("HELLO".to_string()));
+    assert_eq!(extensions.get("language_code"), Some("de"));
+    assert_eq!(extensions.get("shouted"), Some("true"));
+}
+
+#[tokio::test]
+async fn middleware_short_circuits_update() {
+    assert!(middlewares()
+        .run(update_from_text("buy spam"))
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn wrapped_handler_receives_transformed_update() {
+    let handler = middlewares().wrap(dptree::endpoint(
+        |update: Update, extensions: UpdateExtensions| async move {
+            assert_eq!(text(&update), Some("HELLO".to_string()));
+            assert_eq!(extensions.get("language_code"), Some("de"));
+
+            respond(())
+        },
+    ));
+
+    let result = handler
+        .dispatch(dptree::deps![update_from_text("hello")])
+        .await;
+    assert!(matches!(result, ControlFlow::Break(Ok(()))));
+
+    let result = handler
+        .dispatch(dptree::deps![update_from_text("spam")])
+        .await;
+    assert!(matches!(result, ControlFlow::Continue(_)));
+}
+
+struct Footer;
+
+#[async_trait]
+impl EventMiddleware for Footer {
+    async fn before(&self, event: BlocEvent) -> Option<BlocEvent> {
+        match event {
+            BlocEvent::TextToChatSend { chat_id, .. } if chat_id < 0 => None,
+            BlocEvent::TextToChatSend { chat_id, text } => Some(BlocEvent::TextToChatSend {
+                chat_id,
+                text: format!("{}\n--\nsent by bot", text),
+            }),
+            event => Some(event),
+        }
+    }
+}
+
+struct Audit(Arc<Mutex<Vec<String>>>);
+
+#[async_trait]
+impl EventMiddleware for Audit {
+    async fn before(&self, event: BlocEvent) -> Option<BlocEvent> {
+        Some(event)
+    }
+
+    async fn after(&self, _event: &BlocEvent, states: &[BlocState]) {
+        let mut audit = self.0.lock().unwrap();
+        audit.extend(states.iter().map(|state| state.to_string()));
+    }
+}
+
+#[tokio::test]
+async fn event_middlewares_rewrite_and_veto() {
+    let mut middlewares = EventMiddlewares::default();
+    middlewares.push(Arc::new(Footer));
+
+    let event = BlocEvent::TextToChatSend {
+        chat_id: 10,
+        text: "hello".to_string(),
+    };
+    match middlewares.before(event).await {
+        Some(BlocEvent::TextToChatSend { text, .. }) => {
+            assert_eq!(text, "hello\n--\nsent by bot")
+        }
+        _ => panic!("Expected rewritten text"),
+    }
+
+    let event = BlocEvent::TextToChatSend {
+        chat_id: -100,
+        text: "hello".to_string(),
+    };
+    assert!(middlewares.before(event).await.is_none());
+}
+
+#[tokio::test]
+async fn event_middlewares_observe_states() {
+    let audit = Arc::new(Mutex::new(Vec::new()));
+    let mut middlewares = EventMiddlewares::default();
+    middlewares.push(Arc::new(Audit(audit.clone())));
+
+    let (state_controller, state_stream) = async_channel::unbounded();
+    let event = BlocEvent::GetFile {
+        file_id: "file".to_string(),
+    };
+
+    let (observed_controller, observer) = middlewares.observe(&event, &state_controller);
+    observed_controller
+        .send(BlocState::GetFileUnsuccessful {
+            file_id: "file".to_string(),
+        })
+        .await
+        .unwrap();
+    drop(observed_controller);
+    observer.unwrap().await.unwrap();
+
+    assert!(state_stream.try_recv().is_ok());
+    assert_eq!(
+        *audit.lock().unwrap(),
+        vec!["GetFileUnsuccessful{file_id:file}"]
+    );
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+#[path = "parsed_command_test.rs"]
+mod parsed_command_test;
+
+use teloxide::utils::command::BotCommands;
+
+/// A `/command@BotUsername arguments` message split into its parts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedCommand {
+    pub name: String,
+    pub bot_username: Option<String>,
+    pub args: String,
+    text: String,
+}
+
+impl ParsedCommand {
+    pub fn parse(text: &str) -> Option<ParsedCommand> {
+        let text = text.trim_start();
+        let rest = text.strip_prefix('/')?;
+
+        let (command, args) = match rest.find(char::is_whitespace) {
+            Some(index) => (&rest[..index], rest[index..].trim()),
+            None => (rest, ""),
+        };
+
+        let (name, bot_username) = match command.split_once('@') {
+            Some((name, bot_username)) => (name, Some(bot_username.to_string())),
+            None => (command, None),
+        };
+
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(ParsedCommand {
+            name: name.to_string(),
+            bot_username,
+            args: args.to_string(),
+            text: text.to_string(),
+        })
+    }
+
+    pub fn split_args(&self) -> Vec<String> {
+        self.args.split_whitespace().map(String::from).collect()
+    }
+
+    /// The deep-link payload of `/start <payload>`.
+    pub fn start_payload(&self) -> Option<&str> {
+        if self.name == "start" && !self.args.is_empty() {
+            Some(&self.args)
+        } else {
+            None
+        }
+    }
+
+    /// Commands without a username are addressed to every bot in the chat.
+    pub fn is_addressed_to(&self, bot_username: &str) -> bool {
+        match &self.bot_username {
+            Some(username) => username.eq_ignore_ascii_case(bot_username),
+            None => true,
+        }
+    }
+
+    /// Parses the command into an enum deriving `BotCommands`.
+    pub fn to_typed<C: BotCommands>(&self, bot_username: &str) -> Option<C> {
+        C::parse(&self.text, bot_username).ok()
+    }
+}
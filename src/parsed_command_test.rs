@@ -0,0 +1,71 @@
+use teloxide::utils::command::BotCommands;
+
+use super::ParsedCommand;
+
+#[derive(BotCommands, Debug, PartialEq)]
+#[command(rename = "lowercase")]
+enum Command {
+    Start,
+    Echo(String),
+}
+
+#[test]
+fn command_with_bot_username_and_arguments() {
+    let command = ParsedCommand::parse("/echo@OurBot  hello   world ").unwrap();
+
+    assert_eq!(command.name, "echo");
+    assert_eq!(command.bot_username, Some("OurBot".to_string()));
+    assert_eq!(command.args, "hello   world");
+    assert_eq!(command.split_args(), vec!["hello", "world"]);
+}
+
+#[test]
+fn plain_text_is_not_a_command() {
+    assert_eq!(ParsedCommand::parse("hello"), None);
+    assert_eq!(ParsedCommand::parse("/"), None);
+    assert_eq!(ParsedCommand::parse("/@OurBot"), None);
+}
+
+#[test]
+fn command_is_addressed_by_username() {
+    let ours = ParsedCommand::parse("/start@ourbot").unwrap();
+    let theirs = ParsedCommand::parse("/start@OtherBot").unwrap();
+    let everyone = ParsedCommand::parse("/start").unwrap();
+
+    assert!(ours.is_addressed_to("OurBot"));
+    assert!(!theirs.is_addressed_to("OurBot"));
+    assert!(everyone.is_addressed_to("OurBot"));
+}
+
+#[test]
+fn command_converts_to_typed_enum() {
+    let command = ParsedCommand::parse("/echo@OurBot hello world").unwrap();
+
+    assert_eq!(
+        command.to_typed::<Command>("OurBot"),
+        Some(Command::Echo("hello world".to_string()))
+    );
+    assert_eq!(
+        ParsedCommand::parse("/start")
+            .unwrap()
+            .to_typed::<Command>("OurBot"),
+        Some(Command::Start)
+    );
+}
+
+#[test]
+fn start_payload_is_taken_from_arguments() {
+    let start = ParsedCommand::parse("/start ref_42").unwrap();
+
+    assert_eq!(start.start_payload(), Some("ref_42"));
+    assert_eq!(
+        ParsedCommand::parse("/start").unwrap().start_payload(),
+        None
+    );
+    assert_eq!(
+        ParsedCommand::parse("/help ref_42")
+            .unwrap()
+            .start_payload(),
+        None
+    );
+}
@@ -0,0 +1,157 @@
+#[cfg(test)]
+#[path = "router_test.rs"]
+mod router_test;
+
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use regex::Regex;
+
+use teloxide::types::{CallbackQuery, Message};
+
+use crate::bloc_event::BlocEvent;
+use crate::parsed_command::ParsedCommand;
+
+#[derive(Clone, Debug)]
+pub struct CommandContext {
+    pub message: Message,
+    pub command: ParsedCommand,
+}
+
+#[derive(Clone, Debug)]
+pub struct TextContext {
+    pub message: Message,
+    pub captures: Vec<Option<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CallbackContext {
+    pub callback_query: CallbackQuery,
+    /// Callback data with the registered prefix removed.
+    pub data: String,
+}
+
+type Route<C> = Arc<dyn Fn(C) -> BoxFuture<'static, Vec<BlocEvent>> + Send + Sync>;
+
+/// A matched route together with its context, ready to run.
+#[derive(Clone)]
+pub(crate) struct RouteCall(Arc<dyn Fn() -> BoxFuture<'static, Vec<BlocEvent>> + Send + Sync>);
+
+impl RouteCall {
+    fn new<C: Clone + Send + Sync + 'static>(route: Route<C>, context: C) -> RouteCall {
+        RouteCall(Arc::new(move || route(context.clone())))
+    }
+
+    pub(crate) async fn run(&self) -> Vec<BlocEvent> {
+        (self.0)().await
+    }
+}
+
+/// Handlers registered with `on_command`, `on_text` and `on_callback`. The
+/// events they return are sent to the Bloc, updates without a matching route
+/// are emitted as states.
+#[derive(Clone, Default)]
+pub struct Router {
+    commands: Vec<(String, Route<CommandContext>)>,
+    texts: Vec<(Regex, Route<TextContext>)>,
+    callbacks: Vec<(String, Route<CallbackContext>)>,
+}
+
+impl Router {
+    pub fn new() -> Router {
+        Router::default()
+    }
+
+    pub fn on_command<F, Fut>(mut self, name: impl Into<String>, handler: F) -> Router
+    where
+        F: Fn(CommandContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Vec<BlocEvent>> + Send + 'static,
+    {
+        let name = name.into().trim_start_matches('/').to_lowercase();
+
+        self.commands
+            .push((name, Arc::new(move |context| Box::pin(handler(context)))));
+        self
+    }
+
+    pub fn on_text<F, Fut>(mut self, pattern: Regex, handler: F) -> Router
+    where
+        F: Fn(TextContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Vec<BlocEvent>> + Send + 'static,
+    {
+        self.texts
+            .push((pattern, Arc::new(move |context| Box::pin(handler(context)))));
+        self
+    }
+
+    pub fn on_callback<F, Fut>(mut self, prefix: impl Into<String>, handler: F) -> Router
+    where
+        F: Fn(CallbackContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Vec<BlocEvent>> + Send + 'static,
+    {
+        self.callbacks.push((
+            prefix.into(),
+            Arc::new(move |context| Box::pin(handler(context))),
+        ));
+        self
+    }
+
+    pub fn has_command(&self, name: &str) -> bool {
+        self.commands
+            .iter()
+            .any(|(command, _)| command.eq_ignore_ascii_case(name))
+    }
+
+    pub(crate) fn match_command(
+        &self,
+        message: Message,
+        command: ParsedCommand,
+    ) -> Option<RouteCall> {
+        let (_, route) = self
+            .commands
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&command.name))?;
+
+        Some(RouteCall::new(
+            route.clone(),
+            CommandContext { message, command },
+        ))
+    }
+
+    pub(crate) fn match_text(&self, message: Message) -> Option<RouteCall> {
+        let text = message.text()?;
+
+        self.texts.iter().find_map(|(pattern, route)| {
+            let captures = pattern
+                .captures(text)?
+                .iter()
+                .map(|capture| capture.map(|capture| capture.as_str().to_string()))
+                .collect();
+
+            Some(RouteCall::new(
+                route.clone(),
+                TextContext {
+                    message: message.clone(),
+                    captures,
+                },
+            ))
+        })
+    }
+
+    pub(crate) fn match_callback(&self, callback_query: CallbackQuery) -> Option<RouteCall> {
+        let data = callback_query.data.clone()?;
+
+        self.callbacks.iter().find_map(|(prefix, route)| {
+            let data = data.strip_prefix(prefix.as_str())?.to_string();
+
+            Some(RouteCall::new(
+                route.clone(),
+                CallbackContext {
+                    callback_query: callback_query.clone(),
+                    data,
+                },
+            ))
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use regex::Regex;
+
+use crate::bloc_event::BlocEvent;
+use crate::parsed_command::ParsedCommand;
+use crate::test_fixtures::{callback_query_from_data, message_from_text};
+
+use super::Router;
+
+fn texts(events: Vec<BlocEvent>) -> Vec<String> {
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            BlocEvent::TextToChatSend { text, .. } => Some(text),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn command_route_receives_arguments() {
+    let router = Router::new().on_command("/echo", |context| async move {
+        vec![BlocEvent::TextToChatSend {
+            chat_id: context.message.chat.id.0,
+            text: context.command.args,
+        }]
+    });
+
+    let message = message_from_text("/echo hello");
+    let command = ParsedCommand::parse("/echo hello").unwrap();
+
+    assert!(router.has_command("ECHO"));
+
+    let call = router.match_command(message, command).unwrap();
+    assert_eq!(texts(call.run().await), vec!["hello"]);
+
+    let other = ParsedCommand::parse("/start").unwrap();
+    assert!(router
+        .match_command(message_from_text("/start"), other)
+        .is_none());
+}
+
+#[tokio::test]
+async fn text_route_receives_captures() {
+    let router = Router::new().on_text(
+        Regex::new(r"^order (\d+)$").unwrap(),
+        |context| async move {
+            vec![BlocEvent::TextToChatSend {
+                chat_id: context.message.chat.id.0,
+                text: context.captures[1].clone().unwrap(),
+            }]
+        },
+    );
+
+    let call = router.match_text(message_from_text("order 15")).unwrap();
+    assert_eq!(texts(call.run().await), vec!["15"]);
+
+    assert!(router.match_text(message_from_text("hello")).is_none());
+}
+
+#[tokio::test]
+async fn callback_route_matches_prefix() {
+    let router = Router::new().on_callback("page:", |context| async move {
+        vec![BlocEvent::TextToChatSend {
+            chat_id: 10,
+            text: context.data,
+        }]
+    });
+
+    let call = router
+        .match_callback(callback_query_from_data("page:3"))
+        .unwrap();
+    assert_eq!(texts(call.run().await), vec!["3"]);
+
+    assert!(router
+        .match_callback(callback_query_from_data("vote:1"))
+        .is_none());
+}
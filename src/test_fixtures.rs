@@ -0,0 +1,115 @@
+//! Fixtures shared by the unit tests of several modules.
+
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use teloxide::types::{CallbackQuery, Message, Update, User};
+
+use crate::dialogue::DialogueInput;
+use crate::parsed_command::ParsedCommand;
+
+/// User JSON with `extra` fields, e.g. `language_code`, added.
+pub(crate) fn user_json(user_id: u64, extra: Value) -> Value {
+    merge(
+        json!({"id": user_id, "is_bot": false, "first_name": "User"}),
+        extra,
+    )
+}
+
+pub(crate) fn user(user_id: u64) -> User {
+    serde_json::from_value(user_json(user_id, json!({}))).unwrap()
+}
+
+/// Message from `user_id` in a `private`, `group` or `supergroup` chat, with
+/// `content` such as `{"text": ...}` or `{"photo": [...]}` added.
+pub(crate) fn message_json(user_id: u64, chat_id: i64, chat_type: &str, content: Value) -> Value {
+    let chat = match chat_type {
+        "private" => json!({"id": chat_id, "type": chat_type, "first_name": "User"}),
+        _ => json!({"id": chat_id, "type": chat_type, "title": "Group"}),
+    };
+
+    merge(
+        json!({
+            "message_id": 1,
+            "date": 1600000000,
+            "chat": chat,
+            "from": user_json(user_id, json!({})),
+        }),
+        content,
+    )
+}
+
+pub(crate) fn message(user_id: u64, chat_id: i64, chat_type: &str, content: Value) -> Message {
+    serde_json::from_value(message_json(user_id, chat_id, chat_type, content)).unwrap()
+}
+
+/// Private message from user 10.
+pub(crate) fn message_from_text(text: &str) -> Message {
+    message(10, 10, "private", json!({ "text": text }))
+}
+
+/// Callback query from `user_id`, attached to `message` when given.
+pub(crate) fn callback_query_json(user_id: u64, data: &str, message: Option<Value>) -> Value {
+    let mut callback_query = json!({
+        "id": "42",
+        "from": user_json(user_id, json!({})),
+        "chat_instance": "instance",
+        "data": data,
+    });
+
+    if let Some(message) = message {
+        callback_query["message"] = message;
+    }
+
+    callback_query
+}
+
+pub(crate) fn callback_query(user_id: u64, data: &str, message: Option<Value>) -> CallbackQuery {
+    serde_json::from_value(callback_query_json(user_id, data, message)).unwrap()
+}
+
+/// Callback query from user 10 without the message it belongs to.
+pub(crate) fn callback_query_from_data(data: &str) -> CallbackQuery {
+    callback_query(10, data, None)
+}
+
+/// Update of `kind`, e.g. `message` or `callback_query`.
+pub(crate) fn update(kind: &str, content: Value) -> Update {
+    let json = json!({"update_id": 1, kind: content}).to_string();
+
+    // `Update` only parses its kind from a string, a `Value` ends up as
+    // `UpdateKind::Error`.
+    serde_json::from_str(&json).unwrap()
+}
+
+/// Private message from `user_id` as an update.
+pub(crate) fn update_from_text(user_id: u64, chat_id: i64, text: &str) -> Update {
+    update(
+        "message",
+        message_json(user_id, chat_id, "private", json!({ "text": text })),
+    )
+}
+
+/// `message_from_text` as a dialogue input, a command when the text is one.
+pub(crate) fn dialogue_input(text: &str) -> DialogueInput {
+    let message = message_from_text(text);
+
+    match ParsedCommand::parse(text) {
+        Some(command) => DialogueInput::Command { message, command },
+        None => DialogueInput::Message(message),
+    }
+}
+
+/// Unique per test process, the caller removes what it creates there.
+pub(crate) fn temporary_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("telegram_bot_{}_{}", name, std::process::id()))
+}
+
+fn merge(mut base: Value, extra: Value) -> Value {
+    if let (Some(base), Value::Object(extra)) = (base.as_object_mut(), extra) {
+        base.extend(extra);
+    }
+
+    base
+}
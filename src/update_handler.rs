@@ -0,0 +1,500 @@
+//! Update handling shared by every Bloc, whatever bot it sends with.
+
+use std::time::Instant;
+
+use async_channel::Sender;
+
+use teloxide::dispatching::{DpHandlerDescription, UpdateFilterExt};
+use teloxide::dptree::HandlerDescription;
+use teloxide::types::{
+    CallbackQuery, ChatMemberUpdated, ChosenInlineResult, InlineQuery, Me, Message, Poll,
+    PollAnswer, Update,
+};
+use teloxide::{dptree, respond};
+
+use crate::access_control::AccessControl;
+use crate::bloc::BotUpdateHandler;
+use crate::bloc_event::BlocEvent;
+use crate::bloc_state::BlocState;
+use crate::captcha::Captcha;
+use crate::command_registry::CommandRegistry;
+use crate::dialogue::{DialogueInput, Dialogues};
+use crate::flood_control::FloodControl;
+use crate::middleware::UpdateMiddlewares;
+use crate::parsed_command::ParsedCommand;
+use crate::router::{RouteCall, Router};
+
+/// Description for filters put in front of a handler. Unlike the endpoint
+/// descriptions they keep the allowed updates the handler hints to polling.
+pub(crate) fn entry_description() -> DpHandlerDescription {
+    DpHandlerDescription::entry()
+}
+
+pub(crate) fn default_update_handler() -> BotUpdateHandler {
+    let route_handler = |call: RouteCall, event_controller: Sender<BlocEvent>| async move {
+        for event in call.run().await {
+            event_controller
+                .send(event)
+                .await
+                .expect("Can't send routed event.");
+        }
+
+        respond(())
+    };
+
+    let message_dialogue = |message: Message,
+                            me: Me,
+                            dialogues: Dialogues,
+                            state_controller: Sender<BlocState>| async move {
+        let input = match message.text().and_then(ParsedCommand::parse) {
+            Some(command) if !command.is_addressed_to(me.username()) => return None,
+            Some(command) => DialogueInput::Command {
+                message: message.clone(),
+                command,
+            },
+            None => DialogueInput::Message(message.clone()),
+        };
+
+        dialogues
+            .handle(message.chat.id.0, input, &state_controller)
+            .await
+    };
+
+    let callback_query_dialogue =
+        |callback_query: CallbackQuery,
+         dialogues: Dialogues,
+         state_controller: Sender<BlocState>| async move {
+            let chat_id = callback_query.message.as_ref()?.chat.id.0;
+            let input = DialogueInput::CallbackQuery(callback_query);
+
+            dialogues.handle(chat_id, input, &state_controller).await
+        };
+
+    let dialogue_handler = |events: Vec<BlocEvent>, event_controller: Sender<BlocEvent>| async move {
+        for event in events {
+            event_controller
+                .send(event)
+                .await
+                .expect("Can't send dialogue event.");
+        }
+
+        respond(())
+    };
+
+    let command_route = |message: Message, command: ParsedCommand, router: Router| {
+        router.match_command(message, command)
+    };
+
+    let text_route = |message: Message, router: Router| router.match_text(message);
+
+    let callback_route =
+        |callback_query: CallbackQuery, router: Router| router.match_callback(callback_query);
+
+    let message_handler = |message: Message, state_controller: Sender<BlocState>| async move {
+        let state = BlocState::Message {
+            message: Box::new(message),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let new_chat_members_filter = |message: Message| message.new_chat_members().is_some();
+
+    let new_chat_members_handler =
+        |message: Message,
+         captcha: Option<Captcha>,
+         state_controller: Sender<BlocState>,
+         event_controller: Sender<BlocEvent>| async move {
+            let chat_id = message.chat.id.0;
+            let members = message.new_chat_members().unwrap_or_default().to_vec();
+
+            if let Some(captcha) = captcha.filter(|_| !message.chat.is_private()) {
+                let events =
+                    captcha.challenge(chat_id, &members, &event_controller, &state_controller);
+
+                for event in events {
+                    event_controller
+                        .send(event)
+                        .await
+                        .expect("Can't send captcha event.");
+                }
+            }
+
+            let state = BlocState::NewChatMembers {
+                chat_id,
+                members,
+                message: Box::new(message),
+            };
+
+            state_controller
+                .send(state)
+                .await
+                .expect("Can't send update state.");
+
+            respond(())
+        };
+
+    let captcha_answer = |callback_query: CallbackQuery,
+                          captcha: Option<Captcha>,
+                          state_controller: Sender<BlocState>| async move {
+        captcha?.answer(&callback_query, &state_controller).await
+    };
+
+    let left_chat_member_filter = |message: Message| message.left_chat_member().is_some();
+
+    let left_chat_member_handler = |message: Message, state_controller: Sender<BlocState>| async move {
+        let state = BlocState::LeftChatMember {
+            chat_id: message.chat.id.0,
+            member: message.left_chat_member().unwrap().clone(),
+            message: Box::new(message),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let command_parser = |message: Message| message.text().and_then(ParsedCommand::parse);
+
+    let foreign_command_filter =
+        |command: ParsedCommand, me: Me| !command.is_addressed_to(me.username());
+
+    // Commands addressed to another bot in a group are dropped.
+    let foreign_command_handler = || async move { respond(()) };
+
+    let start_parser =
+        |command: ParsedCommand| command.start_payload().map(|payload| payload.to_string());
+
+    // Plain `/start` without a payload stays a `Command`.
+    let start_handler = |message: Message, payload: String, state_controller: Sender<BlocState>| async move {
+        let state = BlocState::Start {
+            message: Box::new(message),
+            payload,
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    // With an empty registry every command is routed as known.
+    let unknown_command_filter = |command: ParsedCommand, commands: CommandRegistry| {
+        !commands.is_empty() && !commands.contains(&command.name)
+    };
+
+    let unknown_command_handler =
+        |message: Message, command: ParsedCommand, state_controller: Sender<BlocState>| async move {
+            let state = BlocState::UnknownCommand {
+                message: Box::new(message),
+                command,
+            };
+
+            state_controller
+                .send(state)
+                .await
+                .expect("Can't send update state.");
+
+            respond(())
+        };
+
+    let command_handler = |message: Message,
+                           command: ParsedCommand,
+                           state_controller: Sender<BlocState>| async move {
+        let state = BlocState::Command {
+            message: Box::new(message),
+            command,
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let edited_message_handler = |message: Message, state_controller: Sender<BlocState>| async move {
+        let state = BlocState::EditedMessage {
+            message: Box::new(message),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let channel_post_handler = |message: Message, state_controller: Sender<BlocState>| async move {
+        let state = BlocState::ChannelPost {
+            message: Box::new(message),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let edited_channel_post_handler = |message: Message, state_controller: Sender<BlocState>| async move {
+        let state = BlocState::EditedChannelPost {
+            message: Box::new(message),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let poll_handler = |poll: Poll, state_controller: Sender<BlocState>| async move {
+        let state = BlocState::Poll {
+            poll: Box::new(poll),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let poll_answer_handler = |poll_answer: PollAnswer, state_controller: Sender<BlocState>| async move {
+        let state = BlocState::PollAnswer {
+            poll_answer: Box::new(poll_answer),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let my_chat_member_handler =
+        |chat_member_updated: ChatMemberUpdated, state_controller: Sender<BlocState>| async move {
+            let state = BlocState::MyChatMember {
+                chat_id: chat_member_updated.chat.id.0,
+                old_status: chat_member_updated.old_chat_member.status(),
+                new_status: chat_member_updated.new_chat_member.status(),
+                chat_member_updated: Box::new(chat_member_updated),
+            };
+
+            state_controller
+                .send(state)
+                .await
+                .expect("Can't send update state.");
+
+            respond(())
+        };
+
+    let chat_member_handler = |chat_member_updated: ChatMemberUpdated,
+                               state_controller: Sender<BlocState>| async move {
+        let state = BlocState::ChatMember {
+            chat_id: chat_member_updated.chat.id.0,
+            old_status: chat_member_updated.old_chat_member.status(),
+            new_status: chat_member_updated.new_chat_member.status(),
+            chat_member_updated: Box::new(chat_member_updated),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let callback_query_handler =
+        |callback_query: CallbackQuery, state_controller: Sender<BlocState>| async move {
+            let state = BlocState::CallbackQuery {
+                callback_query: Box::new(callback_query),
+            };
+
+            state_controller
+                .send(state)
+                .await
+                .expect("Can't send update state.");
+
+            respond(())
+        };
+
+    let inline_query_handler = |inline_query: InlineQuery, state_controller: Sender<BlocState>| async move {
+        let state = BlocState::InlineQuery {
+            inline_query: Box::new(inline_query),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        respond(())
+    };
+
+    let chosen_inline_result_handler =
+        |chosen_inline_result: ChosenInlineResult, state_controller: Sender<BlocState>| async move {
+            let state = BlocState::ChosenInlineResult {
+                chosen_inline_result: Box::new(chosen_inline_result),
+            };
+
+            state_controller
+                .send(state)
+                .await
+                .expect("Can't send update state.");
+
+            respond(())
+        };
+
+    dptree::entry()
+        .branch(
+            Update::filter_message()
+                .branch(dptree::filter(new_chat_members_filter).endpoint(new_chat_members_handler))
+                .branch(dptree::filter(left_chat_member_filter).endpoint(left_chat_member_handler))
+                .branch(dptree::filter_map_async(message_dialogue).endpoint(dialogue_handler))
+                .branch(
+                    dptree::filter_map(command_parser)
+                        .branch(
+                            dptree::filter(foreign_command_filter)
+                                .endpoint(foreign_command_handler),
+                        )
+                        .branch(dptree::filter_map(command_route).endpoint(route_handler))
+                        .branch(dptree::filter_map(start_parser).endpoint(start_handler))
+                        .branch(
+                            dptree::filter(unknown_command_filter)
+                                .endpoint(unknown_command_handler),
+                        )
+                        .branch(dptree::endpoint(command_handler)),
+                )
+                .branch(dptree::filter_map(text_route).endpoint(route_handler))
+                .branch(dptree::endpoint(message_handler)),
+        )
+        .branch(Update::filter_edited_message().endpoint(edited_message_handler))
+        .branch(Update::filter_channel_post().endpoint(channel_post_handler))
+        .branch(Update::filter_edited_channel_post().endpoint(edited_channel_post_handler))
+        .branch(
+            Update::filter_callback_query()
+                .branch(dptree::filter_map_async(captcha_answer).endpoint(dialogue_handler))
+                .branch(
+                    dptree::filter_map_async(callback_query_dialogue).endpoint(dialogue_handler),
+                )
+                .branch(dptree::filter_map(callback_route).endpoint(route_handler))
+                .branch(dptree::endpoint(callback_query_handler)),
+        )
+        .branch(Update::filter_inline_query().endpoint(inline_query_handler))
+        .branch(Update::filter_chosen_inline_result().endpoint(chosen_inline_result_handler))
+        .branch(Update::filter_poll().endpoint(poll_handler))
+        .branch(Update::filter_poll_answer().endpoint(poll_answer_handler))
+        .branch(Update::filter_my_chat_member().endpoint(my_chat_member_handler))
+        .branch(Update::filter_chat_member().endpoint(chat_member_handler))
+}
+
+/// Runs the middlewares, the access control and the flood control in
+/// front of `handler`.
+pub(crate) fn prepare_handler(
+    middlewares: &UpdateMiddlewares,
+    handler: BotUpdateHandler,
+) -> BotUpdateHandler {
+    let access_filter = |update: Update,
+                         me: Me,
+                         access_control: AccessControl,
+                         state_controller: Sender<BlocState>,
+                         event_controller: Sender<BlocEvent>| async move {
+        let reason = match access_control.check_update(&update, me.username()) {
+            Ok(()) => return true,
+            Err(reason) => reason,
+        };
+
+        for event in access_control.denied_events(&update, &reason) {
+            event_controller
+                .send(event)
+                .await
+                .expect("Can't send access denied reply.");
+        }
+
+        let state = BlocState::AccessDenied {
+            chat_id: update.chat().map(|chat| chat.id.0),
+            user_id: update.user().map(|user| user.id.0),
+            reason,
+            update: Box::new(update),
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        false
+    };
+
+    let flood_filter = |update: Update,
+                        flood_control: FloodControl,
+                        state_controller: Sender<BlocState>,
+                        event_controller: Sender<BlocEvent>| async move {
+        let user_id = update.user().map(|user| user.id.0);
+        let chat_id = update.chat().map(|chat| chat.id.0);
+
+        let throttled = match flood_control.hit(user_id, chat_id, Instant::now()) {
+            Ok(()) => return true,
+            Err(throttled) => throttled,
+        };
+
+        // Only the first dropped update is reported, the rest are dropped silently.
+        if !throttled.first {
+            return false;
+        }
+
+        if let (Some(chat_id), Some(text)) = (chat_id, flood_control.cooldown_notice()) {
+            let event = BlocEvent::TextToChatSend {
+                chat_id,
+                text: text.to_string(),
+            };
+
+            event_controller
+                .send(event)
+                .await
+                .expect("Can't send cooldown notice.");
+        }
+
+        let state = BlocState::Throttled {
+            chat_id,
+            user_id,
+            scope: throttled.scope,
+            retry_after: throttled.retry_after,
+        };
+
+        state_controller
+            .send(state)
+            .await
+            .expect("Can't send update state.");
+
+        false
+    };
+
+    let guarded_handler = dptree::filter_async_with_description(entry_description(), access_filter)
+        .chain(dptree::filter_async_with_description(
+            entry_description(),
+            flood_filter,
+        ))
+        .chain(handler);
+
+    middlewares.wrap(guarded_handler)
+}
@@ -0,0 +1,202 @@
+#[cfg(test)]
+#[path = "uploaded_files_test.rs"]
+mod uploaded_files_test;
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::download::to_hex;
+use crate::media_source::MediaSource;
+
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Remembers the `file_id` Telegram returned for uploaded local files, keyed by
+/// path and content hash, so the same bytes are not uploaded twice.
+///
+/// Only files sent as `MediaSource::path` through `FileToChatSend` or
+/// `MediaSourcesToChatSend` are reused. `MediaToChatSend` always uploads again,
+/// because `InputFile` doesn't expose the path it was created from.
+#[derive(Clone)]
+pub struct UploadedFiles {
+    storage_path: Option<PathBuf>,
+    file_ids: Arc<Mutex<HashMap<String, String>>>,
+    write_lock: Arc<AsyncMutex<()>>,
+}
+
+impl UploadedFiles {
+    pub fn new() -> UploadedFiles {
+        UploadedFiles {
+            storage_path: None,
+            file_ids: Arc::new(Mutex::new(HashMap::new())),
+            write_lock: Arc::new(AsyncMutex::new(())),
+        }
+    }
+
+    /// Loads the mapping from a JSON file and saves it there on every change.
+    pub fn open(storage_path: impl Into<PathBuf>) -> io::Result<UploadedFiles> {
+        let storage_path = storage_path.into();
+
+        let file_ids = match std::fs::read(&storage_path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(error) => return Err(error),
+        };
+
+        Ok(UploadedFiles {
+            storage_path: Some(storage_path),
+            file_ids: Arc::new(Mutex::new(file_ids)),
+            write_lock: Arc::new(AsyncMutex::new(())),
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.file_ids.lock().unwrap().get(key).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.file_ids.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub async fn insert(&self, key: String, file_id: String) -> io::Result<()> {
+        self.update(|file_ids| {
+            file_ids.insert(key, file_id);
+        })
+        .await
+    }
+
+    /// Drops a `file_id` Telegram no longer accepts, e.g. one uploaded with
+    /// another bot token.
+    pub async fn forget(&self, key: &str) -> io::Result<()> {
+        self.update(|file_ids| {
+            file_ids.remove(key);
+        })
+        .await
+    }
+
+    async fn update(&self, change: impl FnOnce(&mut HashMap<String, String>)) -> io::Result<()> {
+        // Held until the rename, so snapshots reach the file in the order they
+        // were taken and never share the temporary file.
+        let _write_guard = self.write_lock.lock().await;
+
+        let content = {
+            let mut file_ids = self.file_ids.lock().unwrap();
+            change(&mut file_ids);
+
+            serde_json::to_vec(&*file_ids)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+        };
+
+        match &self.storage_path {
+            Some(storage_path) => save(storage_path, &content).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Looks up a local file. `None` for sources without a path.
+    pub(crate) async fn resolve(&self, source: &MediaSource) -> Option<UploadKey> {
+        let key = match upload_key(source.get_path()?).await {
+            Ok(key) => key,
+            Err(error) => {
+                let log_message = format!("Can't hash file for upload. Error: {:?}.", error);
+                log::warn!("{}", log_message);
+
+                return None;
+            }
+        };
+        let file_id = self.get(&key);
+
+        Some(UploadKey { key, file_id })
+    }
+
+    /// Stores the `file_id` of a fresh upload. Sends by a stored `file_id`
+    /// are left as they are.
+    pub(crate) async fn remember(&self, upload: Option<UploadKey>, file_id: Option<String>) {
+        let (upload, file_id) = match (upload, file_id) {
+            (Some(upload), Some(file_id)) if upload.file_id.is_none() => (upload, file_id),
+            _ => return,
+        };
+
+        if let Err(error) = self.insert(upload.key, file_id).await {
+            let log_message = format!("Can't save uploaded file ids. Error: {:?}.", error);
+            log::warn!("{}", log_message);
+        }
+    }
+
+    /// Forgets the stored `file_id`s a failed send used, so the files are
+    /// uploaded again. Returns whether there were any.
+    pub(crate) async fn forget_stored(&self, uploads: &mut [Option<UploadKey>]) -> bool {
+        let mut forgotten = false;
+
+        for upload in uploads.iter_mut().flatten() {
+            if upload.file_id.take().is_none() {
+                continue;
+            }
+
+            if let Err(error) = self.forget(&upload.key).await {
+                let log_message = format!("Can't save uploaded file ids. Error: {:?}.", error);
+                log::warn!("{}", log_message);
+            }
+            forgotten = true;
+        }
+
+        forgotten
+    }
+}
+
+/// Key of a local file, with the `file_id` stored under it if the same
+/// content was uploaded before.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct UploadKey {
+    pub(crate) key: String,
+    pub(crate) file_id: Option<String>,
+}
+
+impl Default for UploadedFiles {
+    fn default() -> Self {
+        UploadedFiles::new()
+    }
+}
+
+/// `source` as it should be sent: by its stored `file_id` or uploaded again.
+pub(crate) fn with_stored_file_id(source: MediaSource, upload: Option<&UploadKey>) -> MediaSource {
+    match upload.and_then(|upload| upload.file_id.clone()) {
+        Some(file_id) => source.with_file_id(file_id),
+        None => source,
+    }
+}
+
+pub(crate) async fn upload_key(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; HASH_BUFFER_SIZE];
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{}:{}", path.display(), to_hex(&hasher.finalize())))
+}
+
+pub(crate) async fn save(storage_path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut temporary_path = storage_path.as_os_str().to_owned();
+    temporary_path.push(".part");
+
+    fs::write(&temporary_path, content).await?;
+    fs::rename(&temporary_path, storage_path).await
+}
@@ -0,0 +1,108 @@
+use sha2::{Digest, Sha256};
+
+use crate::download::to_hex;
+use crate::media_source::MediaSource;
+use crate::test_fixtures::temporary_path;
+
+use super::{upload_key, UploadedFiles, HASH_BUFFER_SIZE};
+
+#[tokio::test]
+async fn upload_key_changes_with_content() {
+    let path = temporary_path("upload_key");
+
+    tokio::fs::write(&path, b"first").await.unwrap();
+    let first_key = upload_key(&path).await.unwrap();
+
+    tokio::fs::write(&path, b"second").await.unwrap();
+    let second_key = upload_key(&path).await.unwrap();
+
+    let _ = tokio::fs::remove_file(&path).await;
+
+    assert!(first_key.starts_with(&path.display().to_string()));
+    assert_ne!(first_key, second_key);
+}
+
+#[tokio::test]
+async fn upload_key_hashes_whole_file() {
+    let path = temporary_path("upload_key_large");
+    let content = vec![7; HASH_BUFFER_SIZE * 2 + 1];
+    tokio::fs::write(&path, &content).await.unwrap();
+
+    let key = upload_key(&path).await.unwrap();
+    let _ = tokio::fs::remove_file(&path).await;
+
+    assert!(key.ends_with(&to_hex(&Sha256::digest(&content))));
+}
+
+#[tokio::test]
+async fn known_file_is_resolved_to_file_id() {
+    let path = temporary_path("resolve");
+    tokio::fs::write(&path, b"chart").await.unwrap();
+
+    let uploaded_files = UploadedFiles::new();
+
+    let upload = uploaded_files.resolve(&MediaSource::path(&path)).await;
+    assert_eq!(upload.as_ref().unwrap().file_id, None);
+
+    uploaded_files
+        .remember(upload.clone(), Some("AgACAgIAAxk".to_string()))
+        .await;
+
+    let stored = uploaded_files.resolve(&MediaSource::path(&path)).await;
+    let _ = tokio::fs::remove_file(&path).await;
+
+    assert_eq!(stored.as_ref().unwrap().key, upload.unwrap().key);
+    assert_eq!(stored.unwrap().file_id, Some("AgACAgIAAxk".to_string()));
+    assert_eq!(uploaded_files.len(), 1);
+}
+
+#[tokio::test]
+async fn rejected_file_id_is_forgotten() {
+    let storage_path = temporary_path("rejected_uploaded_files.json");
+    let path = temporary_path("rejected");
+    tokio::fs::write(&path, b"chart").await.unwrap();
+
+    let uploaded_files = UploadedFiles::open(&storage_path).unwrap();
+    let upload = uploaded_files.resolve(&MediaSource::path(&path)).await;
+    uploaded_files
+        .remember(upload, Some("expired".to_string()))
+        .await;
+
+    let mut uploads = vec![
+        uploaded_files.resolve(&MediaSource::path(&path)).await,
+        None,
+    ];
+    assert!(uploaded_files.forget_stored(&mut uploads).await);
+    assert_eq!(uploads[0].as_ref().unwrap().file_id, None);
+    assert!(!uploaded_files.forget_stored(&mut uploads).await);
+
+    uploaded_files
+        .remember(uploads.remove(0), Some("AgACAgIAAxk".to_string()))
+        .await;
+
+    let reopened = UploadedFiles::open(&storage_path).unwrap();
+    let stored = reopened.resolve(&MediaSource::path(&path)).await;
+    let _ = tokio::fs::remove_file(&path).await;
+    let _ = tokio::fs::remove_file(&storage_path).await;
+
+    assert_eq!(stored.unwrap().file_id, Some("AgACAgIAAxk".to_string()));
+}
+
+#[tokio::test]
+async fn file_ids_survive_reopening() {
+    let storage_path = temporary_path("uploaded_files.json");
+
+    let uploaded_files = UploadedFiles::open(&storage_path).unwrap();
+    uploaded_files
+        .insert("chart.png:abc".to_string(), "AgACAgIAAxk".to_string())
+        .await
+        .unwrap();
+
+    let reopened = UploadedFiles::open(&storage_path).unwrap();
+    let _ = tokio::fs::remove_file(&storage_path).await;
+
+    assert_eq!(
+        reopened.get("chart.png:abc"),
+        Some("AgACAgIAAxk".to_string())
+    );
+}
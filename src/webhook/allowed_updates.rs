@@ -0,0 +1,22 @@
+use teloxide::types::AllowedUpdate;
+
+/// Telegram does not send `chat_member` updates unless they are requested
+/// explicitly, so webhooks ask for every update kind.
+pub fn allowed_updates() -> Vec<AllowedUpdate> {
+    vec![
+        AllowedUpdate::Message,
+        AllowedUpdate::EditedMessage,
+        AllowedUpdate::ChannelPost,
+        AllowedUpdate::EditedChannelPost,
+        AllowedUpdate::InlineQuery,
+        AllowedUpdate::ChosenInlineResult,
+        AllowedUpdate::CallbackQuery,
+        AllowedUpdate::ShippingQuery,
+        AllowedUpdate::PreCheckoutQuery,
+        AllowedUpdate::Poll,
+        AllowedUpdate::PollAnswer,
+        AllowedUpdate::MyChatMember,
+        AllowedUpdate::ChatMember,
+        AllowedUpdate::ChatJoinRequest,
+    ]
+}
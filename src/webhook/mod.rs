@@ -1,3 +1,4 @@
+mod allowed_updates;
 mod handle_rejection;
 mod health_check;
 
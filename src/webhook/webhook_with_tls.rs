@@ -12,9 +12,11 @@ use teloxide::adaptors::AutoSend;
 use teloxide::dispatching::stop_token::AsyncStopToken;
 use teloxide::dispatching::update_listeners;
 use teloxide::dispatching::update_listeners::StatefulListener;
+use teloxide::payloads::SetWebhookSetters;
 use teloxide::prelude::Requester;
 use teloxide::types::Update;
 
+use super::allowed_updates::allowed_updates;
 use super::handle_rejection::handle_rejection;
 
 pub async fn webhook_with_tls(
@@ -25,7 +27,10 @@ pub async fn webhook_with_tls(
     key_path: &str,
 ) -> impl update_listeners::UpdateListener<Infallible> {
     let url = Url::parse(web_hook_url).unwrap();
-    bot.set_webhook(url).await.expect("Cannot setup a webhook");
+    bot.set_webhook(url)
+        .allowed_updates(allowed_updates())
+        .await
+        .expect("Cannot setup a webhook");
 
     let (sender, receiver) = mpsc::unbounded_channel();
 
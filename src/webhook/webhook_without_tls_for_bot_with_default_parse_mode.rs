@@ -12,9 +12,11 @@ use teloxide::adaptors::{AutoSend, DefaultParseMode};
 use teloxide::dispatching::stop_token::AsyncStopToken;
 use teloxide::dispatching::update_listeners;
 use teloxide::dispatching::update_listeners::StatefulListener;
+use teloxide::payloads::SetWebhookSetters;
 use teloxide::prelude::Requester;
 use teloxide::types::Update;
 
+use super::allowed_updates::allowed_updates;
 use super::handle_rejection::handle_rejection;
 
 pub async fn webhook_without_tls_for_bot_with_default_parse_mode(
@@ -23,7 +25,10 @@ pub async fn webhook_without_tls_for_bot_with_default_parse_mode(
     web_hook_url: &str,
 ) -> impl update_listeners::UpdateListener<Infallible> {
     let url = Url::parse(web_hook_url).unwrap();
-    bot.set_webhook(url).await.expect("Cannot setup a webhook");
+    bot.set_webhook(url)
+        .allowed_updates(allowed_updates())
+        .await
+        .expect("Cannot setup a webhook");
 
     let (sender, receiver) = mpsc::unbounded_channel();
 